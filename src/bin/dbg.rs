@@ -1,10 +1,12 @@
 use std::{
+    cell::Cell,
+    collections::VecDeque,
     path::PathBuf,
     time::{Duration, Instant},
 };
 
 use anyhow::Context;
-use clap::{Parser, error::ErrorKind};
+use clap::{CommandFactory, Parser, error::ErrorKind};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
     DefaultTerminal, Frame,
@@ -19,12 +21,13 @@ use ratatui::{
 };
 
 use chip8_rust::{
-    debugger::{Cli, Command, Executor},
+    debugger::{BreakpointAction, Cli, Command, Executor, Operand, u12_parse},
     emu::{Chip8, Chip8Runner, Chip8RunnerResult, DISPLAY_X, DISPLAY_Y},
-    u4,
+    u4, u12,
 };
 
-const KEY_MAP: [KeyCode; 16] = [
+/// Default mapping from keyboard keys to CHIP-8 hex keypad (0x0-0xF).
+const DEFAULT_KEY_MAP: [KeyCode; 16] = [
     KeyCode::Char('x'), // 0x0
     KeyCode::Char('1'), // 0x1
     KeyCode::Char('2'), // 0x2
@@ -43,58 +46,134 @@ const KEY_MAP: [KeyCode; 16] = [
     KeyCode::Char('v'), // 0xF
 ];
 
-// Key release events are not fired in terminals on Linux.
+/// Number of instructions shown in the code panel before and after the program counter.
+const CODE_BEFORE_PC: u16 = 4;
+const CODE_AFTER_PC: u16 = 8;
+
+/// Width of the code panel, including borders.
+const CODE_WIDTH: u16 = 36 + 2;
+
+/// Maximum number of search matches listed in the output.
+const MAX_FIND_RESULTS: usize = 64;
+
+/// Maximum number of unknown instructions listed by the lint command.
+const MAX_LINT_RESULTS: usize = 64;
+
+/// Maximum number of instructions listed by the profile command.
+const MAX_PROFILE_RESULTS: usize = 16;
+
+// Key release events are not fired by most terminals on Linux.
 // To handle this, we implement a timeout after which we consider a key released.
-const KEY_RELEASE_TIMEOUT: Duration = Duration::from_millis(50);
+const DEFAULT_KEY_TIMEOUT_MS: u64 = 50;
 
-/// A widget for displaying output messages with scrolling support.
-struct OutputBox {
+// A timed out key still stays pressed for at least two 60Hz frames,
+// so a ROM polling the keypad once per frame can't miss a quick tap.
+const DEFAULT_KEY_MIN_HOLD_MS: u64 = 34;
+
+/// Maximum number of entries kept in the output log.
+const MAX_OUTPUT_ENTRIES: usize = 100;
+
+/// A single command output in the log.
+struct OutputEntry {
     content: String,
     is_error: bool,
-    scroll_state: ScrollbarState,
+}
+
+/// A widget for displaying a log of output messages with scrolling support.
+struct OutputBox {
+    entries: VecDeque<OutputEntry>,
+    /// Scroll position in lines, `None` to show the start of the newest entry.
+    scroll: Option<usize>,
+    /// Line the newest entry starts at and total line count, as of the last render.
+    layout: Cell<(usize, usize)>,
 }
 
 impl OutputBox {
     fn new(content: String) -> Self {
-        Self {
-            scroll_state: ScrollbarState::new(content.lines().count()),
-            is_error: false,
-            content,
-        }
+        let mut output = Self {
+            entries: VecDeque::new(),
+            scroll: None,
+            layout: Cell::new((0, 0)),
+        };
+        output.set(content, false);
+        output
     }
 
+    /// Append an entry to the log and scroll to it.
     fn set(&mut self, content: String, is_error: bool) {
-        *self = Self::new(content);
-        self.is_error = is_error;
+        if self.entries.len() == MAX_OUTPUT_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(OutputEntry { content, is_error });
+        self.scroll = None;
     }
 
     fn set_str(&mut self, content: &str, is_error: bool) {
         self.set(content.to_string(), is_error);
     }
 
+    /// Remove all entries from the log.
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.scroll = None;
+    }
+
+    fn position(&self) -> usize {
+        self.scroll.unwrap_or(self.layout.get().0)
+    }
+
     /// Scroll up.
     fn up(&mut self) {
-        self.scroll_state.prev();
+        self.scroll = Some(self.position().saturating_sub(1));
     }
 
     /// Scroll down.
     fn down(&mut self) {
-        self.scroll_state.next();
+        let last_line = self.layout.get().1.saturating_sub(1);
+        self.scroll = Some((self.position() + 1).min(last_line));
     }
 }
 
 impl Widget for &OutputBox {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        Paragraph::new(self.content.as_str())
-            .wrap(Wrap { trim: true })
-            .scroll((self.scroll_state.get_position() as u16, 0))
+        let wrap = Wrap { trim: true };
+        let width = area.width.saturating_sub(2);
+
+        // Entries are separated by an empty line, errors are shown in red
+        let mut lines: Vec<Line> = Vec::new();
+        let mut newest_start = 0;
+        for (idx, entry) in self.entries.iter().enumerate() {
+            if idx > 0 {
+                lines.push(Line::from(""));
+            }
+            if idx == self.entries.len() - 1 {
+                newest_start = Paragraph::new(lines.clone()).wrap(wrap).line_count(width);
+            }
+
+            let style = if entry.is_error {
+                Style::new().fg(Color::Red)
+            } else {
+                Style::new()
+            };
+            lines.extend(
+                entry
+                    .content
+                    .lines()
+                    .map(|line| Line::styled(line.to_string(), style)),
+            );
+        }
+
+        let paragraph = Paragraph::new(lines).wrap(wrap);
+        let total = paragraph.line_count(width);
+        self.layout.set((newest_start, total));
+        let position = self.position().min(total.saturating_sub(1));
+
+        let is_error = self.entries.back().is_some_and(|entry| entry.is_error);
+        paragraph
+            .scroll((position as u16, 0))
             .block(
                 Block::bordered()
-                    .border_style(Style::new().fg(if self.is_error {
-                        Color::Red
-                    } else {
-                        Color::White
-                    }))
+                    .border_style(Style::new().fg(if is_error { Color::Red } else { Color::White }))
                     .title(" Output "),
             )
             .render(area, buf);
@@ -102,7 +181,7 @@ impl Widget for &OutputBox {
         Scrollbar::new(ScrollbarOrientation::VerticalRight).render(
             area,
             buf,
-            &mut self.scroll_state.clone(),
+            &mut ScrollbarState::new(total).position(position),
         );
     }
 }
@@ -114,24 +193,56 @@ struct App {
     should_quit: bool,
     last_tick: Instant,
     last_command: Option<Command>,
-    key_press_times: [Option<Instant>; 16],
+    /// Previously entered command lines, oldest first
+    history: Vec<String>,
+    /// Index into `history` while browsing it
+    history_index: Option<usize>,
+    /// Input typed before browsing the history, restored when browsing past the newest entry
+    history_draft: String,
+    /// For each pressed key, the time of its first press event and of its latest press or
+    /// repeat event
+    key_press_times: [Option<(Instant, Instant)>; 16],
+    /// Time after the last press or repeat event at which a key is considered released
+    key_timeout: Duration,
+    /// Shortest time a key stays pressed when released by the timeout
+    key_min_hold: Duration,
+    /// Set once the terminal sends a key release event, after which the timeout isn't needed
+    has_release_events: bool,
+    /// Mapping from keyboard keys to CHIP-8 hex keypad (0x0-0xF).
+    key_map: [KeyCode; 16],
 }
 
 impl App {
-    fn new(rom: &[u8]) -> anyhow::Result<Self> {
+    fn new(args: &Args) -> anyhow::Result<Self> {
+        let rom = std::fs::read(&args.rom_path).context("Failed to read ROM")?;
         let mut chip8 = Chip8::default();
-        chip8
-            .load(rom)
-            .context("Failed to load ROM into CHIP-8 memory")?;
+        chip8.load(&rom).context("Failed to load ROM")?;
+
+        let mut executor = Executor::new(Chip8Runner::new(chip8));
+        for &addr in &args.breakpoints {
+            executor.execute(Command::Breakpoint {
+                action: BreakpointAction::Set { addr: addr.into() },
+            })?;
+        }
+        if args.run {
+            executor.run();
+        }
 
         Ok(Self {
-            executor: Executor::new(Chip8Runner::new(chip8)),
+            executor,
             input: String::new(),
             output: OutputBox::new("Enter 'help' for a list of commands.".to_string()),
             should_quit: false,
             last_tick: Instant::now(),
             last_command: None,
+            history: Vec::new(),
+            history_index: None,
+            history_draft: String::new(),
             key_press_times: [None; 16],
+            key_timeout: Duration::from_millis(args.key_timeout_ms),
+            key_min_hold: Duration::from_millis(args.key_min_hold_ms),
+            has_release_events: false,
+            key_map: args.keymap.unwrap_or(DEFAULT_KEY_MAP),
         })
     }
 
@@ -143,7 +254,7 @@ impl App {
 
             // Handles execution when debugger is in running mode
             match self.executor.poll(dt) {
-                Ok(Chip8RunnerResult::HitBreakpoint) => {
+                Ok(Chip8RunnerResult::HitBreakpoint { .. }) => {
                     self.output.set_str("Hit breakpoint", false)
                 }
                 Err(e) => self.output.set(e.to_string(), true),
@@ -171,13 +282,18 @@ impl App {
 
     /// Checks for key release timeouts and updates keypad state accordingly.
     ///
-    /// This is necessary because terminals do not send key release events.
+    /// This is necessary because most terminals do not send key release events.
+    /// Terminals that do send them are honored directly, without a timeout.
     fn check_key_timeout(&mut self) {
+        if self.has_release_events {
+            return;
+        }
+
         let now = Instant::now();
 
         for (idx, press_time) in self.key_press_times.iter_mut().enumerate() {
-            if let Some(time) = press_time
-                && now.duration_since(*time) > KEY_RELEASE_TIMEOUT
+            if let Some((first, last)) = *press_time
+                && key_timed_out(now, first, last, self.key_timeout, self.key_min_hold)
             {
                 *press_time = None;
                 self.executor
@@ -204,9 +320,21 @@ impl App {
                     self.output.set_str("Paused", false);
                 }
                 _ => {
-                    if let Some(idx) = KEY_MAP.iter().position(|&k| k == key.code) {
-                        self.executor.runner_mut().set_key(u4::new(idx as u8), true);
-                        self.key_press_times[idx] = Some(Instant::now());
+                    if let Some(idx) = self.key_map.iter().position(|&k| k == key.code) {
+                        let pressed = key.kind != KeyEventKind::Release;
+                        if !pressed {
+                            self.has_release_events = true;
+                        }
+
+                        self.executor
+                            .runner_mut()
+                            .set_key(u4::new(idx as u8), pressed);
+                        let now = Instant::now();
+                        self.key_press_times[idx] = pressed.then(|| {
+                            // Repeat events keep the key held, but don't restart the hold
+                            let first = self.key_press_times[idx].map_or(now, |(first, _)| first);
+                            (first, now)
+                        });
                     }
                 }
             }
@@ -218,24 +346,111 @@ impl App {
                 KeyCode::Enter => {
                     self.handle_enter();
                 }
+                KeyCode::Up if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                    self.history_prev();
+                }
+                KeyCode::Down if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                    self.history_next();
+                }
                 KeyCode::Up => {
                     self.output.up();
                 }
                 KeyCode::Down => {
                     self.output.down();
                 }
+                KeyCode::Tab => {
+                    self.complete_command();
+                }
                 KeyCode::Char(c) => {
                     self.input.push(c);
+                    self.history_index = None;
                 }
                 KeyCode::Backspace => {
                     self.input.pop();
+                    self.history_index = None;
                 }
                 _ => {}
             }
         }
     }
 
+    /// Complete the command name being typed against the command names and aliases.
+    ///
+    /// A single match is filled in, multiple matches are completed to their common prefix
+    /// and listed in the output.
+    fn complete_command(&mut self) {
+        // Only the first token is completed
+        if self.input.contains(char::is_whitespace) {
+            return;
+        }
+
+        let mut cli = Cli::command();
+        cli.build();
+        let mut candidates: Vec<String> = cli
+            .get_subcommands()
+            .flat_map(|command| {
+                std::iter::once(command.get_name()).chain(command.get_visible_aliases())
+            })
+            .filter(|name| name.starts_with(self.input.as_str()))
+            .map(str::to_string)
+            .collect();
+        candidates.sort();
+
+        match candidates.as_slice() {
+            [] => {}
+            [name] => self.input = format!("{name} "),
+            [first, rest @ ..] => {
+                let common_len = rest.iter().fold(first.len(), |len, name| {
+                    first
+                        .bytes()
+                        .zip(name.bytes())
+                        .take(len)
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                });
+                self.input = first[..common_len].to_string();
+                self.output.set(candidates.join("  "), false);
+            }
+        }
+    }
+
+    /// Replace the input with the previous command line from the history.
+    fn history_prev(&mut self) {
+        let idx = match self.history_index {
+            Some(idx) => idx.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => {
+                self.history_draft = self.input.clone();
+                self.history.len() - 1
+            }
+        };
+
+        self.history_index = Some(idx);
+        self.input = self.history[idx].clone();
+    }
+
+    /// Replace the input with the next command line from the history,
+    /// or the input typed before browsing once past the newest one.
+    fn history_next(&mut self) {
+        let Some(idx) = self.history_index else {
+            return;
+        };
+
+        if idx + 1 < self.history.len() {
+            self.history_index = Some(idx + 1);
+            self.input = self.history[idx + 1].clone();
+        } else {
+            self.history_index = None;
+            self.input = std::mem::take(&mut self.history_draft);
+        }
+    }
+
     fn handle_enter(&mut self) {
+        if !self.input.trim().is_empty() && self.history.last() != Some(&self.input) {
+            self.history.push(self.input.clone());
+        }
+        self.history_index = None;
+
         if self.input.is_empty() && self.last_command.is_some() {
             self.execute_command(self.last_command.clone().unwrap());
         } else {
@@ -261,6 +476,9 @@ impl App {
                 chip8_rust::debugger::CommandResult::Ok => {
                     self.output.set_str("OK", false);
                 }
+                chip8_rust::debugger::CommandResult::Clear => {
+                    self.output.clear();
+                }
                 chip8_rust::debugger::CommandResult::Quit => {
                     self.should_quit = true;
                 }
@@ -271,7 +489,12 @@ impl App {
                         self.output.set(
                             breakpoints
                                 .iter()
-                                .map(|b| format!("Breakpoint: {b:#05X}\n"))
+                                .map(|(addr, condition)| match condition {
+                                    Some(condition) => {
+                                        format!("Breakpoint: {addr:#05X} if {condition}\n")
+                                    }
+                                    None => format!("Breakpoint: {addr:#05X}\n"),
+                                })
                                 .collect(),
                             false,
                         )
@@ -292,24 +515,111 @@ impl App {
                         false,
                     );
                 }
-                chip8_rust::debugger::CommandResult::Disasm {
-                    instructions,
-                    offset,
-                } => {
+                chip8_rust::debugger::CommandResult::Disasm { instructions, code } => {
+                    // Mark the instruction at the program counter and likely data
+                    let pc = usize::from(self.executor.get_pc()) as u16;
                     self.output.set(
                         instructions
                             .iter()
+                            .map(|(addr, ins, opcode)| {
+                                let data = if code.contains(addr) { "" } else { " (data)" };
+                                let marker = if *addr == pc { " <- PC" } else { "" };
+                                format!("{addr:03X}: {ins:04X} - {opcode}{data}{marker}\n")
+                            })
+                            .collect(),
+                        false,
+                    );
+                }
+                chip8_rust::debugger::CommandResult::Registers {
+                    v,
+                    i,
+                    pc,
+                    dt,
+                    st,
+                    stack,
+                } => {
+                    let mut text =
+                        format!("PC: {pc:03X}  I: {i:03X}  DT: {dt:02X}  ST: {st:02X}\n");
+                    for (row, values) in v.chunks(4).enumerate() {
+                        let regs: Vec<String> = values
+                            .iter()
+                            .enumerate()
+                            .map(|(col, value)| format!("V{:X}: {value:02X}", row * 4 + col))
+                            .collect();
+                        text += &format!("{}\n", regs.join("  "));
+                    }
+                    let stack: Vec<String> =
+                        stack.iter().map(|addr| format!("{addr:03X}")).collect();
+                    text += &format!("Stack: [{}]\n", stack.join(", "));
+                    self.output.set(text, false);
+                }
+                chip8_rust::debugger::CommandResult::Profile { counts } => {
+                    if counts.is_empty() {
+                        self.output.set_str("No instructions executed", false);
+                    } else {
+                        let total: u64 = counts.iter().map(|(_, count)| count).sum();
+                        self.output.set(
+                            counts
+                                .iter()
+                                .take(MAX_PROFILE_RESULTS)
+                                .map(|(name, count)| {
+                                    let percent = *count as f64 / total as f64 * 100.0;
+                                    format!("{name:<18} {count:>10} {percent:5.1}%\n")
+                                })
+                                .collect(),
+                            false,
+                        );
+                    }
+                }
+                chip8_rust::debugger::CommandResult::FindResult { addresses } => {
+                    if addresses.is_empty() {
+                        self.output.set_str("No matches found", false);
+                    } else {
+                        let mut text: String = addresses
+                            .iter()
+                            .take(MAX_FIND_RESULTS)
+                            .map(|addr| format!("Found at: {addr:03X}\n"))
+                            .collect();
+                        if addresses.len() > MAX_FIND_RESULTS {
+                            text += &format!("{} more\n", addresses.len() - MAX_FIND_RESULTS);
+                        }
+                        self.output.set(text, false);
+                    }
+                }
+                chip8_rust::debugger::CommandResult::Glyph { address, data } => {
+                    let addr = usize::from(address);
+                    self.output.set(
+                        data.iter()
                             .enumerate()
-                            .map(|(i, (ins, opcode))| {
-                                format!(
-                                    "{:03X}: {ins:04X} - {opcode:X?}\n",
-                                    offset.wrapping_add((i * 2) as u16)
-                                )
+                            .map(|(row, byte)| {
+                                let pixels: String = (0..8)
+                                    .map(|bit| if byte & (0x80 >> bit) != 0 { '#' } else { '.' })
+                                    .collect();
+                                format!("{:03X}: {byte:02X} {pixels}\n", addr + row)
                             })
                             .collect(),
                         false,
                     );
                 }
+                chip8_rust::debugger::CommandResult::LintResult { issues } => {
+                    if issues.is_empty() {
+                        self.output.set_str("No unknown instructions found", false);
+                    } else {
+                        let mut text: String = issues
+                            .iter()
+                            .take(MAX_LINT_RESULTS)
+                            .map(|(addr, value)| format!("Unknown at {addr:03X}: {value:04X}\n"))
+                            .collect();
+                        if issues.len() > MAX_LINT_RESULTS {
+                            text += &format!("{} more\n", issues.len() - MAX_LINT_RESULTS);
+                        }
+                        self.output.set(text, false);
+                    }
+                }
+                chip8_rust::debugger::CommandResult::UnknownOpcode { address } => {
+                    self.output
+                        .set(format!("Stopped at unknown opcode at {address:03X}"), false);
+                }
             },
             Err(e) => {
                 self.output.set(e.to_string(), true);
@@ -321,7 +631,7 @@ impl App {
 impl Widget for &App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         // Check if we have enough space
-        const MIN_WIDTH: u16 = DISPLAY_X as u16 + 2 + 15 + 2;
+        const MIN_WIDTH: u16 = DISPLAY_X as u16 + 2 + CODE_WIDTH + 15 + 2;
         const MIN_HEIGHT: u16 = DISPLAY_Y as u16 + 2 + 1 + 2 + 1 + 2;
         if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
             let center = area.centered(Constraint::Length(45), Constraint::Length(3));
@@ -338,8 +648,9 @@ impl Widget for &App {
             return;
         }
 
-        let [left, right] = Layout::horizontal([
+        let [left, code, right] = Layout::horizontal([
             Constraint::Min(DISPLAY_X as u16 + 2),
+            Constraint::Length(CODE_WIDTH),
             Constraint::Length(15 + 2),
         ])
         .areas(area);
@@ -360,6 +671,7 @@ impl Widget for &App {
         .areas(right);
 
         self.render_display(display, buf);
+        self.render_code(code, buf);
         self.render_state(state, buf);
         self.render_registers(registers, buf);
         self.render_keypad(keypad, buf);
@@ -371,33 +683,77 @@ impl Widget for &App {
 
 impl App {
     fn render_display(&self, area: Rect, buf: &mut Buffer) {
-        let text: Vec<Line> = self
+        let text = Text::styled(
+            self.executor.runner().render_ascii('█', ' '),
+            Style::default().green(),
+        );
+
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .block(Block::bordered().title(" Display "))
+            .render(area, buf);
+    }
+
+    /// Render the instructions around the program counter, highlighting the current one.
+    fn render_code(&self, area: Rect, buf: &mut Buffer) {
+        let pc = usize::from(self.executor.get_pc()) as u16;
+        // Keep the window aligned with PC, clamping it at the start of memory
+        let start = pc.saturating_sub(CODE_BEFORE_PC * 2);
+        let len = pc - start + (CODE_AFTER_PC + 1) * 2;
+
+        let lines: Vec<Line> = self
             .executor
-            .get_display()
+            .runner()
+            .chip8_ref()
+            .disassemble_range(start, len)
             .iter()
-            .map(|row| {
-                row.iter()
-                    .map(|pixel| {
-                        Span::styled(if *pixel { "█" } else { " " }, Style::default().green())
-                    })
-                    .collect()
+            .map(|(addr, ins, opcode)| {
+                let line = format!("{addr:03X}: {ins:04X} {opcode}");
+                // Breakpoint lines are red, unless PC is on them
+                let has_breakpoint =
+                    u12::try_new(*addr).is_some_and(|addr| self.executor.has_breakpoint(addr));
+                if *addr == pc {
+                    Line::styled(line, Style::default().fg(Color::Black).bg(Color::Yellow))
+                } else if has_breakpoint {
+                    Line::styled(line, Style::default().fg(Color::Red))
+                } else {
+                    Line::from(line)
+                }
             })
             .collect();
 
-        Paragraph::new(text)
-            .alignment(Alignment::Center)
-            .block(Block::bordered().title(" Display "))
+        Paragraph::new(lines)
+            .block(Block::bordered().title(" Code "))
             .render(area, buf);
     }
 
     fn render_registers(&self, area: Rect, buf: &mut Buffer) {
+        let changed = self.executor.changed_registers();
+        // Registers changed by the last step or run are highlighted
+        let register = |label: String, value: String, operand: Operand| {
+            let style = if changed.contains(&operand) {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Span::styled(format!("{label}: {value}"), style)
+        };
+
         let mut lines = Vec::new();
 
-        lines.push(Line::from(format!(
-            "PC: {:03X}  I: {:03X}",
-            self.executor.get_pc(),
-            self.executor.get_i()
-        )));
+        lines.push(Line::from(vec![
+            register(
+                "PC".into(),
+                format!("{:03X}", self.executor.get_pc()),
+                Operand::Pc,
+            ),
+            Span::raw("  "),
+            register(
+                "I".into(),
+                format!("{:03X}", self.executor.get_i()),
+                Operand::I,
+            ),
+        ]));
         lines.push(Line::from(format!(
             "DT: {:02X}   ST: {:02X}",
             self.executor.get_delay_timer(),
@@ -407,13 +763,19 @@ impl App {
 
         let v = self.executor.get_v();
         for idx in 0..8 {
-            lines.push(Line::from(format!(
-                "V{:X}: {:02X}   V{:X}: {:02X}",
-                idx,
-                v[idx],
-                idx + 8,
-                v[idx + 8]
-            )));
+            lines.push(Line::from(vec![
+                register(
+                    format!("V{idx:X}"),
+                    format!("{:02X}", v[idx]),
+                    Operand::V(u4::new(idx as u8)),
+                ),
+                Span::raw("   "),
+                register(
+                    format!("V{:X}", idx + 8),
+                    format!("{:02X}", v[idx + 8]),
+                    Operand::V(u4::new(idx as u8 + 8)),
+                ),
+            ]));
         }
 
         Paragraph::new(lines)
@@ -456,7 +818,11 @@ impl App {
     }
 
     fn render_state(&self, area: Rect, buf: &mut Buffer) {
-        let (text, color) = if self.executor.is_running() {
+        let (text, color) = if self.executor.is_running() && self.executor.runner().is_idle() {
+            ("IDLE LOOP", Color::Cyan)
+        } else if self.executor.is_running() && self.executor.runner().is_waiting_for_key() {
+            ("WAITING KEY", Color::Cyan)
+        } else if self.executor.is_running() {
             ("RUNNING", Color::Green)
         } else {
             ("PAUSED", Color::Yellow)
@@ -511,6 +877,7 @@ impl App {
 /// Throughout the program all values are displayed in hex.
 /// Input values can be decimal or hex (with 0x prefix).
 /// When paused, Escape or Ctrl+C is used to exit the debugger and up arrow/down arrow to scroll output.
+/// Ctrl+up arrow/Ctrl+down arrow browse previously entered commands, Tab completes command names.
 /// When paused, enter executes the last command if input is empty.
 /// When running, keys 1-4, Q-R, A-F, Z-V map to CHIP-8 keys, and Escape pauses the debugger.
 #[derive(Parser)]
@@ -518,13 +885,71 @@ impl App {
 struct Args {
     /// Path to the CHIP-8 ROM file
     rom_path: PathBuf,
+
+    /// Keypad mapping as 16 distinct keys (letters or digits), one per CHIP-8 key
+    /// in order 0x0-0xF. Defaults to x123qweasdzc4rfv.
+    #[arg(long, value_parser = parse_keymap)]
+    keymap: Option<[KeyCode; 16]>,
+
+    /// Set a breakpoint at an address before execution starts. Can be repeated.
+    #[arg(long = "break", value_name = "ADDR", value_parser = u12_parse)]
+    breakpoints: Vec<u12>,
+
+    /// Start running immediately instead of paused
+    #[arg(long)]
+    run: bool,
+
+    /// Milliseconds after the last key event before a key is considered released, as most
+    /// terminals don't report releases. Too low and held keys drop out between key repeats,
+    /// too high and keys feel sticky. Unused if the terminal reports key releases.
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_KEY_TIMEOUT_MS)]
+    key_timeout_ms: u64,
+
+    /// Minimum milliseconds a key stays pressed when released by the key timeout, so quick
+    /// taps aren't missed by ROMs that only check the keypad once per frame.
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_KEY_MIN_HOLD_MS)]
+    key_min_hold_ms: u64,
+}
+
+/// Returns true if a key without release events should be considered released at `now`.
+///
+/// `first_press` is when the key was first pressed and `last_event` its latest press or
+/// repeat event. The key is released once no event arrived for `timeout`, but not before it
+/// was held for `min_hold`.
+fn key_timed_out(
+    now: Instant,
+    first_press: Instant,
+    last_event: Instant,
+    timeout: Duration,
+    min_hold: Duration,
+) -> bool {
+    now.duration_since(last_event) > timeout && now.duration_since(first_press) >= min_hold
+}
+
+/// Parses a keypad mapping of 16 distinct letter or digit keys.
+fn parse_keymap(s: &str) -> Result<[KeyCode; 16], String> {
+    if let Some(c) = s.chars().find(|c| !c.is_ascii_alphanumeric()) {
+        return Err(format!("unsupported key '{c}', expected a letter or digit"));
+    }
+
+    let key_map: [KeyCode; 16] = s
+        .chars()
+        .map(|c| KeyCode::Char(c.to_ascii_lowercase()))
+        .collect::<Vec<_>>()
+        .try_into()
+        .map_err(|keys: Vec<_>| format!("expected 16 keys, got {}", keys.len()))?;
+
+    if (1..16).any(|i| key_map[..i].contains(&key_map[i])) {
+        return Err("keys must be distinct".to_string());
+    }
+
+    Ok(key_map)
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    let rom = std::fs::read(&args.rom_path).context("Failed to read ROM file")?;
-    let mut app = App::new(&rom).context("Failed to initialize application")?;
+    let mut app = App::new(&args).context("Failed to initialize application")?;
 
     let mut terminal = ratatui::init();
     let app_result = app.run(&mut terminal);
@@ -532,3 +957,90 @@ fn main() -> anyhow::Result<()> {
 
     app_result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn tap_is_held_for_min_hold() {
+        let (press, min_hold) = (Instant::now(), ms(100));
+
+        // The timeout passed, but the key wasn't held long enough yet
+        assert!(!key_timed_out(
+            press + ms(60),
+            press,
+            press,
+            TIMEOUT,
+            min_hold
+        ));
+        assert!(!key_timed_out(
+            press + ms(99),
+            press,
+            press,
+            TIMEOUT,
+            min_hold
+        ));
+        assert!(key_timed_out(
+            press + ms(100),
+            press,
+            press,
+            TIMEOUT,
+            min_hold
+        ));
+    }
+
+    #[test]
+    fn repeats_keep_key_held() {
+        let press = Instant::now();
+        let min_hold = ms(34);
+
+        // Repeats every 30ms keep the key pressed well past the minimum hold
+        let last_repeat = press + ms(300);
+        for now in (0..=300).step_by(30).map(|t| press + ms(t) + ms(20)) {
+            let last = now - ms(20);
+            assert!(!key_timed_out(now, press, last, TIMEOUT, min_hold));
+        }
+        assert!(!key_timed_out(
+            last_repeat + ms(50),
+            press,
+            last_repeat,
+            TIMEOUT,
+            min_hold
+        ));
+        assert!(key_timed_out(
+            last_repeat + ms(51),
+            press,
+            last_repeat,
+            TIMEOUT,
+            min_hold
+        ));
+    }
+
+    #[test]
+    fn timeout_applies_when_longer_than_min_hold() {
+        let (press, min_hold) = (Instant::now(), ms(34));
+
+        // Held for the minimum, but the timeout keeps the key pressed
+        assert!(!key_timed_out(
+            press + ms(40),
+            press,
+            press,
+            TIMEOUT,
+            min_hold
+        ));
+        assert!(key_timed_out(
+            press + ms(51),
+            press,
+            press,
+            TIMEOUT,
+            min_hold
+        ));
+    }
+}
@@ -1,26 +1,50 @@
-use std::{path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::Context;
 use clap::Parser;
-use pixels::{Pixels, SurfaceTexture};
+use pixels::{Pixels, PixelsBuilder, SurfaceTexture, wgpu};
 use rodio::{OutputStream, OutputStreamBuilder, Sink, Source, source::SquareWave};
 use winit::{
     application::ApplicationHandler,
     dpi::LogicalSize,
     event::{ElementState, KeyEvent, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    keyboard::{Key, KeyCode, NamedKey},
-    window::{Window, WindowId},
+    keyboard::{Key, KeyCode, NamedKey, PhysicalKey},
+    window::{Fullscreen, Window, WindowId},
 };
 
-use chip8_rust::emu::{Chip8, Chip8Runner, DISPLAY_X, DISPLAY_Y, Display};
+use chip8_rust::emu::{
+    Chip8, Chip8Runner, DEFAULT_CPU_HZ, DISPLAY_X, DISPLAY_Y, Display, SoundEvent, display_coords,
+};
 use chip8_rust::u4;
 
-/// The rate at which pixels fade out (phosphor decay).
-const DISPLAY_PHOSPHOR_RATE: f32 = 10.0;
+/// An RGB color.
+type Rgb = [u8; 3];
+
+/// The time advanced by a single frame step while paused.
+const FRAME_STEP_DT: f32 = 1.0 / 60.0;
+
+/// The amount the speed multiplier changes per key press.
+const SPEED_STEP: f32 = 0.1;
+/// The lowest allowed speed multiplier, so emulation never stalls completely.
+const MIN_SPEED: f32 = 0.1;
+/// How often the frame and cycle rate shown in the window title is updated, in seconds.
+const STATS_INTERVAL: f32 = 0.5;
 
-/// Mapping from physical keyboard keys to CHIP-8 hex keypad (0x0-0xF).
-const KEY_MAP: [KeyCode; 16] = [
+/// Number of trace lines buffered for the printing thread before lines are dropped.
+const TRACE_BUFFER_LINES: usize = 4096;
+
+/// Letter and digit keys taken by hotkeys, which can't be mapped to the keypad.
+/// The other hotkeys (`=`, `-`, function keys...) can't be mapped in the first place.
+const HOTKEY_KEYS: [KeyCode; 2] = [KeyCode::KeyM, KeyCode::Digit0];
+
+/// Default mapping from physical keyboard keys to CHIP-8 hex keypad (0x0-0xF).
+const DEFAULT_KEY_MAP: [KeyCode; 16] = [
     KeyCode::KeyX,   // 0x00
     KeyCode::Digit1, // 0x01
     KeyCode::Digit2, // 0x02
@@ -39,52 +63,304 @@ const KEY_MAP: [KeyCode; 16] = [
     KeyCode::KeyV,   // 0x0F
 ];
 
+/// Gamepad input read from the Linux joystick interface (`/dev/input/js*`).
+///
+/// The d-pad is read from the hat axes and the face buttons from the first four buttons,
+/// which is the layout the kernel reports for Xbox-style controllers.
+#[cfg(feature = "gamepad")]
+mod gamepad {
+    use std::{fs::File, io::Read, path::Path, sync::mpsc};
+
+    use chip8_rust::u4;
+
+    /// Number of gamepad controls that can be mapped: the four d-pad directions followed by
+    /// the A, B, X and Y buttons.
+    pub const CONTROLS: usize = 8;
+
+    /// CHIP-8 key for each gamepad control, in the order of `CONTROLS`, `None` if unmapped.
+    pub type GamepadMap = [Option<u4>; CONTROLS];
+
+    const UP: usize = 0;
+    const DOWN: usize = 1;
+    const LEFT: usize = 2;
+    const RIGHT: usize = 3;
+    /// Index of the first face button control, the face buttons follow in joystick button order.
+    const FACE_BUTTONS: usize = 4;
+
+    /// Joystick event types, see linux/joystick.h.
+    const JS_EVENT_BUTTON: u8 = 0x01;
+    const JS_EVENT_AXIS: u8 = 0x02;
+    const JS_EVENT_INIT: u8 = 0x80;
+    /// Axes of the d-pad hat on Xbox-style controllers.
+    const HAT_X_AXIS: u8 = 6;
+    const HAT_Y_AXIS: u8 = 7;
+
+    /// An open gamepad. Its events are read by a separate thread, so polling never blocks.
+    pub struct Gamepad {
+        receiver: mpsc::Receiver<(usize, bool)>,
+    }
+
+    impl Gamepad {
+        /// Opens a joystick device such as `/dev/input/js0`.
+        pub fn open(path: &Path) -> std::io::Result<Self> {
+            let mut file = File::open(path)?;
+            let (sender, receiver) = mpsc::channel();
+
+            std::thread::spawn(move || {
+                let mut pressed = [false; CONTROLS];
+                let mut event = [0; 8];
+
+                // Stops when the device is unplugged or the emulator exits
+                while file.read_exact(&mut event).is_ok() {
+                    for (control, is_pressed) in decode_event(event) {
+                        if pressed[control] != is_pressed {
+                            pressed[control] = is_pressed;
+                            if sender.send((control, is_pressed)).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            });
+
+            Ok(Self { receiver })
+        }
+
+        /// Returns the controls that were pressed or released since the last poll, in order.
+        pub fn poll(&self) -> impl Iterator<Item = (usize, bool)> + '_ {
+            self.receiver.try_iter()
+        }
+    }
+
+    /// Decodes a `js_event` into the state of the controls it reports on.
+    fn decode_event(event: [u8; 8]) -> Vec<(usize, bool)> {
+        // The first 4 bytes are a timestamp, which isn't needed
+        let value = i16::from_ne_bytes([event[4], event[5]]);
+        let (kind, number) = (event[6] & !JS_EVENT_INIT, event[7]);
+
+        match (kind, number) {
+            (JS_EVENT_BUTTON, 0..4) => vec![(FACE_BUTTONS + number as usize, value != 0)],
+            (JS_EVENT_AXIS, HAT_X_AXIS) => vec![(LEFT, value < 0), (RIGHT, value > 0)],
+            (JS_EVENT_AXIS, HAT_Y_AXIS) => vec![(UP, value < 0), (DOWN, value > 0)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Parses a gamepad mapping of 8 hex digits, or `-` for unmapped controls, for the d-pad
+    /// up, down, left and right followed by the A, B, X and Y buttons.
+    pub fn parse_gamepad_map(s: &str) -> Result<GamepadMap, String> {
+        let map = s
+            .chars()
+            .map(|c| match c {
+                '-' => Ok(None),
+                _ => c
+                    .to_digit(16)
+                    .map(|digit| Some(u4::new(digit as u8)))
+                    .ok_or_else(|| format!("invalid key '{c}', expected a hex digit or '-'")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        map.try_into()
+            .map_err(|map: Vec<_>| format!("expected {CONTROLS} keys, got {}", map.len()))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn js_event(value: i16, kind: u8, number: u8) -> [u8; 8] {
+            let [lo, hi] = value.to_ne_bytes();
+            [0, 0, 0, 0, lo, hi, kind, number]
+        }
+
+        #[test]
+        fn decodes_face_buttons() {
+            assert_eq!(decode_event(js_event(1, JS_EVENT_BUTTON, 0)), [(4, true)]);
+            assert_eq!(decode_event(js_event(0, JS_EVENT_BUTTON, 3)), [(7, false)]);
+            // Initial state events report the same way
+            assert_eq!(
+                decode_event(js_event(1, JS_EVENT_BUTTON | JS_EVENT_INIT, 1)),
+                [(5, true)]
+            );
+            assert!(decode_event(js_event(1, JS_EVENT_BUTTON, 4)).is_empty());
+        }
+
+        #[test]
+        fn decodes_dpad_hat() {
+            let left = decode_event(js_event(i16::MIN, JS_EVENT_AXIS, HAT_X_AXIS));
+            assert_eq!(left, [(LEFT, true), (RIGHT, false)]);
+            let centered = decode_event(js_event(0, JS_EVENT_AXIS, HAT_Y_AXIS));
+            assert_eq!(centered, [(UP, false), (DOWN, false)]);
+            assert!(decode_event(js_event(i16::MAX, JS_EVENT_AXIS, 0)).is_empty());
+        }
+
+        #[test]
+        fn parses_gamepad_map() {
+            let map = parse_gamepad_map("28465---").unwrap();
+            assert_eq!(map[UP], Some(u4::new(0x2)));
+            assert_eq!(map[FACE_BUTTONS], Some(u4::new(0x5)));
+            assert_eq!(map[FACE_BUTTONS + 1], None);
+
+            assert!(parse_gamepad_map("2846").is_err());
+            assert!(parse_gamepad_map("28465--g").is_err());
+        }
+    }
+}
+
+/// The audio output playing the beep tone.
+struct Audio {
+    /// Audio output stream (must be kept alive).
+    _stream: OutputStream,
+    sink: Sink,
+}
+
+impl Audio {
+    /// Opens the default audio output with a paused square wave of the given frequency.
+    fn new(beep_hz: f32, volume: f32) -> anyhow::Result<Self> {
+        let mut stream = OutputStreamBuilder::open_default_stream()
+            .context("Failed to open audio output stream")?;
+        stream.log_on_drop(false);
+
+        // A volume of 0 keeps the sink connected but silent
+        let sink = Sink::connect_new(stream.mixer());
+        sink.pause();
+        sink.append(SquareWave::new(beep_hz).amplify(volume.clamp(0.0, 1.0)));
+
+        Ok(Self {
+            _stream: stream,
+            sink,
+        })
+    }
+}
+
 struct App {
     pixels: Option<Pixels<'static>>,
     window: Option<Arc<Window>>,
     /// Stores the brightness of each pixel (0.0 to 1.0) to implement phosphor decay.
     display_float: Display<f32>,
+    /// The color each pixel had when it was last lit, used while it decays.
+    display_color: Display<Rgb>,
+    /// Pixel colors indexed by the planes a pixel is lit in (0 = unlit background).
+    palette: [Rgb; 4],
+    /// The rate at which pixels fade out (phosphor decay), 0 disables decay.
+    phosphor_rate: f32,
+    /// Scale factor applied to screenshots.
+    screenshot_scale: u32,
+    /// Initial window size as a multiple of the display size.
+    window_scale: u32,
 
-    /// Audio output stream (must be kept alive).
-    _audio_stream: OutputStream,
-    audio_sink: Sink,
+    /// Beep output, `None` when sound is disabled or no audio device could be opened.
+    audio: Option<Audio>,
+
+    /// Gamepad input, `None` when no gamepad could be opened.
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<gamepad::Gamepad>,
+    /// Mapping from gamepad controls to CHIP-8 keys.
+    #[cfg(feature = "gamepad")]
+    gamepad_map: gamepad::GamepadMap,
 
     runner: Chip8Runner,
+    /// Mapping from physical keyboard keys to CHIP-8 hex keypad (0x0-0xF).
+    key_map: [KeyCode; 16],
+    /// CPU speed multiplier relative to the default clock speed.
+    speed: f32,
+    /// Whether the ROM was waiting for a key on the last frame, shown in the window title.
+    waiting_for_key: bool,
+    /// When muted the beep is never played.
+    muted: bool,
+    /// Frames, CPU cycles and seconds counted since the stats were last shown.
+    stats_frames: u32,
+    stats_cycles: usize,
+    stats_elapsed: f32,
+    /// Frame and cycle rate shown in the window title, `None` when hidden.
+    stats: Option<String>,
     /// Used for delta time calculation.
     last_frame_instant: Instant,
+    /// Exit once the ROM reaches a jump-to-self idle loop.
+    exit_on_halt: bool,
 
     /// Stores the result of the application to be returned from main.
     exit_result: anyhow::Result<()>,
 }
 
 impl App {
-    fn new(rom: &[u8]) -> anyhow::Result<Self> {
-        // Initialize audio
-        let mut _audio_stream = OutputStreamBuilder::open_default_stream()
-            .context("Failed to open audio output stream")?;
-        _audio_stream.log_on_drop(false);
+    fn new(args: &Args) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            args.beep_hz > 0.0,
+            "Beep frequency must be positive, got {}",
+            args.beep_hz
+        );
+        anyhow::ensure!(
+            args.phosphor >= 0.0,
+            "Phosphor decay rate must not be negative, got {}",
+            args.phosphor
+        );
 
-        let audio_sink = Sink::connect_new(_audio_stream.mixer());
-        audio_sink.pause();
-        audio_sink.append(SquareWave::new(440.0).amplify(0.5));
+        // Initialize audio, running without sound is preferred over not running at all
+        let audio = if args.no_sound {
+            None
+        } else {
+            match Audio::new(args.beep_hz, args.volume) {
+                Ok(audio) => Some(audio),
+                Err(e) => {
+                    eprintln!("Warning: {e:#}, continuing without sound");
+                    None
+                }
+            }
+        };
+
+        // A missing gamepad only leaves the keyboard, like a missing audio device
+        #[cfg(feature = "gamepad")]
+        let gamepad = match gamepad::Gamepad::open(&args.gamepad) {
+            Ok(gamepad) => Some(gamepad),
+            Err(e) => {
+                eprintln!(
+                    "Warning: Failed to open gamepad {}: {e}, continuing without it",
+                    args.gamepad.display()
+                );
+                None
+            }
+        };
 
         // Initialize CHIP-8
-        let mut chip8 = Chip8::default();
-        chip8
-            .load(rom)
-            .context("Failed to load ROM into CHIP-8 memory")?;
-        let runner = Chip8Runner::new(chip8);
+        let mut chip8 = load_rom(&args.rom_path)?;
+        if let Some(quirks) = chip8.detect_quirks_from_hash() {
+            chip8.set_quirks(quirks);
+        }
+        if args.trace {
+            install_trace(&mut chip8);
+        }
+        let mut runner = Chip8Runner::new(chip8);
+        runner.set_skip_idle_loops(args.skip_idle_loops);
 
         Ok(Self {
             pixels: None,
             window: None,
             display_float: [[0.0; DISPLAY_X]; DISPLAY_Y],
+            display_color: [[args.fg; DISPLAY_X]; DISPLAY_Y],
+            palette: [args.bg, args.fg, args.fg2, args.fg3],
+            phosphor_rate: args.phosphor,
+            screenshot_scale: args.screenshot_scale,
+            window_scale: args.scale,
 
-            _audio_stream,
-            audio_sink,
+            audio,
+
+            #[cfg(feature = "gamepad")]
+            gamepad,
+            #[cfg(feature = "gamepad")]
+            gamepad_map: args.gamepad_map,
 
             runner,
+            key_map: args.keymap.unwrap_or(DEFAULT_KEY_MAP),
+            speed: 1.0,
+            waiting_for_key: false,
+            muted: false,
+            stats_frames: 0,
+            stats_cycles: 0,
+            stats_elapsed: 0.0,
+            stats: None,
             last_frame_instant: Instant::now(),
+            exit_on_halt: args.dump_state.is_some(),
             exit_result: Ok(()),
         })
     }
@@ -92,29 +368,215 @@ impl App {
     fn process_display(&mut self, dt: f32) {
         let buff = self.pixels.as_mut().unwrap().frame_mut();
 
-        for (i, pxl) in buff.chunks_exact_mut(4).enumerate() {
-            let x = i % DISPLAY_X;
-            let y = i / DISPLAY_X;
-
+        for (pxl, (y, x)) in buff.chunks_exact_mut(4).zip(display_coords()) {
             // We use display_float to track the "brightness" of each pixel over time.
             // This allows us to implement a phosphor decay effect where pixels fade out
             // slowly instead of turning off instantly.
-            self.display_float[y][x] = if self.runner.get_display_pixel(y, x) {
+            let planes = self.runner.get_display_planes(y, x);
+            self.display_float[y][x] = if planes != 0 {
                 // Pixel is currently on, set to full brightness
+                self.display_color[y][x] = self.palette[planes as usize];
                 1.0
+            } else if self.phosphor_rate == 0.0 {
+                // Decay is disabled, pixel turns off instantly
+                0.0
             } else {
                 // Pixel is off, but we decay the previous brightness value based on elapsed time
-                (self.display_float[y][x] - DISPLAY_PHOSPHOR_RATE * dt).max(0.0)
+                (self.display_float[y][x] - self.phosphor_rate * dt).clamp(0.0, 1.0)
             };
 
-            let rgba = [0, 0xff, 0, (self.display_float[y][x] * 255.0) as u8];
+            // Blend between background and foreground based on brightness,
+            // so decaying pixels fade towards the background color.
+            let brightness = self.display_float[y][x];
+            let blend = |bg: u8, fg: u8| (bg as f32 + (fg as f32 - bg as f32) * brightness) as u8;
+
+            let (bg, fg) = (self.palette[0], self.display_color[y][x]);
+            let rgba = [
+                blend(bg[0], fg[0]),
+                blend(bg[1], fg[1]),
+                blend(bg[2], fg[2]),
+                0xff,
+            ];
             pxl.copy_from_slice(&rgba);
         }
     }
 
+    /// Saves the current display as a PNG in the working directory.
+    fn save_screenshot(&self) -> anyhow::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("System clock is before the UNIX epoch")?
+            .as_millis();
+        let path = PathBuf::from(format!("chip8-{timestamp}.png"));
+
+        let scale = self.screenshot_scale as usize;
+        let (width, height) = (DISPLAY_X * scale, DISPLAY_Y * scale);
+
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let planes = self.runner.get_display_planes(y / scale, x / scale);
+                rgb.extend_from_slice(&self.palette[planes as usize]);
+            }
+        }
+
+        std::fs::write(&path, encode_png(width as u32, height as u32, &rgb))
+            .context("Failed to write screenshot")?;
+        Ok(path)
+    }
+
+    /// Sets the CPU speed multiplier and shows it in the window title.
+    fn set_speed(&mut self, speed: f32) {
+        // Round to avoid accumulating floating point error when stepping
+        self.speed = ((speed / SPEED_STEP).round() * SPEED_STEP).max(MIN_SPEED);
+        self.runner.set_cpu_hz(DEFAULT_CPU_HZ * self.speed);
+        self.update_title();
+    }
+
+    /// Counts a rendered frame and the CPU cycles run for it, refreshing the shown rates
+    /// every `STATS_INTERVAL`.
+    fn update_stats(&mut self, dt: f32, cycles: usize) {
+        if self.stats.is_none() {
+            return;
+        }
+
+        self.stats_frames += 1;
+        self.stats_cycles += cycles;
+        self.stats_elapsed += dt;
+
+        if self.stats_elapsed >= STATS_INTERVAL {
+            self.stats = Some(format!(
+                "FPS: {:.0}  CPS: {:.0}",
+                self.stats_frames as f32 / self.stats_elapsed,
+                self.stats_cycles as f32 / self.stats_elapsed
+            ));
+            self.stats_frames = 0;
+            self.stats_cycles = 0;
+            self.stats_elapsed = 0.0;
+            self.update_title();
+        }
+    }
+
+    /// Plays or pauses the beep to match the current sound, pause and mute state.
+    fn sync_audio(&self) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+
+        if self.runner.should_beep() && !self.runner.is_paused() && !self.muted {
+            audio.sink.play();
+        } else {
+            audio.sink.pause();
+        }
+    }
+
+    /// Applies the gamepad controls pressed or released since the last frame to the keypad.
+    #[cfg(feature = "gamepad")]
+    fn poll_gamepad(&mut self) {
+        let Some(gamepad) = &self.gamepad else {
+            return;
+        };
+
+        for (control, pressed) in gamepad.poll() {
+            if let Some(key) = self.gamepad_map[control] {
+                self.runner.set_key(key, pressed);
+            }
+        }
+    }
+
+    /// Shows the speed multiplier and whether the ROM is waiting for a key in the window title.
+    fn update_title(&self) {
+        let mut title = format!("chip8-rust ({:.1}x)", self.speed);
+        if self.muted {
+            title += " - muted";
+        }
+        if self.waiting_for_key {
+            title += " - waiting for key";
+        }
+        if let Some(stats) = &self.stats {
+            title += &format!("  {stats}");
+        }
+
+        if let Some(window) = &self.window {
+            window.set_title(&title);
+        }
+    }
+
+    /// Handles emulator hotkeys, returns true if the key was consumed.
+    fn handle_hotkey(&mut self, code: KeyCode) -> anyhow::Result<bool> {
+        match code {
+            KeyCode::F2 => match self.save_screenshot() {
+                Ok(path) => println!("Saved screenshot to {}", path.display()),
+                Err(e) => eprintln!("{e:#}"),
+            },
+            KeyCode::Space => {
+                // When paused the emulator is still rendered, but not updated
+                if self.runner.is_paused() {
+                    self.runner.resume();
+                } else {
+                    self.runner.pause();
+                }
+                self.sync_audio();
+            }
+            KeyCode::F3 => {
+                self.stats = match self.stats {
+                    Some(_) => None,
+                    None => Some("FPS: -  CPS: -".to_string()),
+                };
+                self.stats_frames = 0;
+                self.stats_cycles = 0;
+                self.stats_elapsed = 0.0;
+                self.update_title();
+            }
+            KeyCode::KeyM => {
+                self.muted = !self.muted;
+                self.sync_audio();
+                self.update_title();
+            }
+            KeyCode::F5 => {
+                self.runner.reset();
+                // The fading pixels of the old run are dropped along with their colors
+                self.display_float = [[0.0; DISPLAY_X]; DISPLAY_Y];
+                self.display_color = [[self.palette[1]; DISPLAY_X]; DISPLAY_Y];
+                if let Some(audio) = &self.audio {
+                    audio.sink.pause();
+                }
+                // Avoid a large dt on the first frame after the reset
+                self.last_frame_instant = Instant::now();
+            }
+            KeyCode::F11 => {
+                // The surface is resized by the Resized event that follows. Pixels scales
+                // the image by a whole number and centers it, so the aspect ratio is kept.
+                if let Some(window) = &self.window {
+                    let fullscreen = match window.fullscreen() {
+                        Some(_) => None,
+                        None => Some(Fullscreen::Borderless(None)),
+                    };
+                    window.set_fullscreen(fullscreen);
+                }
+            }
+            KeyCode::Period if self.runner.is_paused() => {
+                // Resume just long enough to advance a single frame
+                self.runner.resume();
+                let result = self.runner.update(FRAME_STEP_DT);
+                self.runner.pause();
+                result.context("Chip8 Execution error")?;
+            }
+            KeyCode::Equal | KeyCode::NumpadAdd => self.set_speed(self.speed + SPEED_STEP),
+            KeyCode::Minus | KeyCode::NumpadSubtract => self.set_speed(self.speed - SPEED_STEP),
+            KeyCode::Digit0 | KeyCode::Numpad0 => self.set_speed(1.0),
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
     fn try_resumed(&mut self, event_loop: &ActiveEventLoop) -> anyhow::Result<()> {
         let window = {
-            let size = LogicalSize::new(DISPLAY_X as u32 * 10, DISPLAY_Y as u32 * 10);
+            let size = LogicalSize::new(
+                DISPLAY_X as u32 * self.window_scale,
+                DISPLAY_Y as u32 * self.window_scale,
+            );
             let min_size = LogicalSize::new(DISPLAY_X as u32, DISPLAY_Y as u32);
 
             Arc::new(
@@ -135,7 +597,11 @@ impl App {
             let surface_texture =
                 SurfaceTexture::new(window_size.width, window_size.height, window.clone());
 
-            let pixels = Pixels::new(DISPLAY_X as u32, DISPLAY_Y as u32, surface_texture)
+            // Pixels scales by the largest integer factor that fits the surface and centers the
+            // image, filling the border with the clear color
+            let pixels = PixelsBuilder::new(DISPLAY_X as u32, DISPLAY_Y as u32, surface_texture)
+                .clear_color(clear_color(self.palette[0]))
+                .build()
                 .context("Failed to create pixels surface")?;
 
             window.request_redraw();
@@ -178,12 +644,38 @@ impl App {
                 let dt = (now - self.last_frame_instant).as_secs_f32();
                 self.last_frame_instant = now;
 
-                self.runner.update(dt).context("Chip8 Execution error")?;
+                #[cfg(feature = "gamepad")]
+                self.poll_gamepad();
 
-                if self.runner.should_beep() {
-                    self.audio_sink.play();
-                } else {
-                    self.audio_sink.pause();
+                let cycles = self
+                    .runner
+                    .update(dt)
+                    .context("Chip8 Execution error")?
+                    .cycles();
+                self.update_stats(dt, cycles);
+
+                if self.exit_on_halt && self.runner.is_idle() {
+                    event_loop.exit();
+                }
+
+                if self.runner.is_waiting_for_key() != self.waiting_for_key {
+                    self.waiting_for_key = !self.waiting_for_key;
+                    self.update_title();
+                }
+
+                if !self.runner.is_paused() {
+                    let event = self.runner.poll_sound_event();
+
+                    if let Some(audio) = &self.audio {
+                        // Playing the square wave faster raises its frequency
+                        audio.sink.set_speed(self.runner.sound_pitch_ratio());
+
+                        match event {
+                            Some(SoundEvent::Start) if !self.muted => audio.sink.play(),
+                            Some(SoundEvent::Stop) => audio.sink.pause(),
+                            _ => {}
+                        }
+                    }
                 }
 
                 self.process_display(dt);
@@ -198,11 +690,18 @@ impl App {
             }
 
             WindowEvent::KeyboardInput { event, .. } => {
-                if let Some(key) = KEY_MAP.iter().position(|&k| k == event.physical_key) {
-                    self.runner.set_key(
-                        u4::new(key as u8),
-                        matches!(event.state, ElementState::Pressed),
-                    );
+                let pressed = matches!(event.state, ElementState::Pressed);
+
+                if pressed
+                    && !event.repeat
+                    && let PhysicalKey::Code(code) = event.physical_key
+                    && self.handle_hotkey(code)?
+                {
+                    return Ok(());
+                }
+
+                if let Some(key) = self.key_map.iter().position(|&k| k == event.physical_key) {
+                    self.runner.set_key(u4::new(key as u8), pressed);
                 }
             }
 
@@ -231,27 +730,375 @@ impl ApplicationHandler for App {
 /// CHIP-8 emulator written in Rust.
 ///
 /// Keys 1-4, Q-R, A-F, Z-V map to CHIP-8 keys.
+/// Space pauses/resumes, period steps a single frame while paused.
+/// +/- change the emulation speed, 0 resets it.
+/// F2 saves a screenshot, F3 shows the frame and cycle rate, F5 restarts the ROM,
+/// F11 toggles fullscreen, M mutes the beep.
 /// Escape is used to exit the emulator.
 #[derive(Parser)]
 #[command(about)]
 struct Args {
     /// Path to the CHIP-8 ROM file
     rom_path: PathBuf,
+
+    /// Frequency of the beep tone in Hz
+    #[arg(long, default_value_t = 440.0)]
+    beep_hz: f32,
+
+    /// Volume of the beep tone (0.0 to 1.0, 0 mutes)
+    #[arg(long, default_value_t = 0.5)]
+    volume: f32,
+
+    /// Don't open an audio device. Without it, the emulator still runs silently if opening
+    /// the audio device fails.
+    #[arg(long)]
+    no_sound: bool,
+
+    /// Foreground (lit pixel) color as a hex RGB value, e.g. FFB000
+    #[arg(long, default_value = "00FF00", value_parser = parse_rgb)]
+    fg: Rgb,
+
+    /// Background (unlit pixel) color as a hex RGB value, e.g. 000080
+    #[arg(long, default_value = "000000", value_parser = parse_rgb)]
+    bg: Rgb,
+
+    /// Color of pixels lit only in the second XO-CHIP plane
+    #[arg(long, default_value = "FF6600", value_parser = parse_rgb)]
+    fg2: Rgb,
+
+    /// Color of pixels lit in both XO-CHIP planes
+    #[arg(long, default_value = "FFFF00", value_parser = parse_rgb)]
+    fg3: Rgb,
+
+    /// Phosphor decay rate in brightness per second, 0 disables decay.
+    ///
+    /// Lower values give a slow CRT-like fade, which hides the flicker of ROMs
+    /// that erase and redraw sprites every frame. Higher values (or 0) give crisp pixels
+    /// at the cost of visible flicker.
+    #[arg(long, default_value_t = 10.0)]
+    phosphor: f32,
+
+    /// Keypad mapping as 16 distinct keys (letters or digits), one per CHIP-8 key
+    /// in order 0x0-0xF. Hotkeys can't be used. Defaults to x123qweasdzc4rfv.
+    #[arg(long, value_parser = parse_keymap)]
+    keymap: Option<[KeyCode; 16]>,
+
+    /// Write the final machine state to a file on exit. The emulator also exits by itself
+    /// once the ROM reaches a jump-to-self idle loop, which many test ROMs end with.
+    #[arg(long, value_name = "PATH")]
+    dump_state: Option<PathBuf>,
+
+    /// Print the disassembled ROM to stdout and exit instead of running it
+    #[arg(long)]
+    disasm: bool,
+
+    /// Print every executed instruction to stderr.
+    ///
+    /// Lines are printed by a separate thread so a slow terminal doesn't lower the frame rate.
+    /// If the terminal can't keep up, lines are dropped and the number of skipped
+    /// instructions is printed instead.
+    #[arg(long)]
+    trace: bool,
+
+    /// Stop running CPU cycles while the ROM is in a jump-to-self loop, only the timers keep
+    /// running. Saves power when a ROM has finished, but checking for the loop adds a little
+    /// work to every cycle.
+    #[arg(long)]
+    skip_idle_loops: bool,
+
+    /// Run the given number of CPU cycles without a window as fast as possible,
+    /// then print the achieved cycles per second and exit
+    #[arg(long, value_name = "CYCLES")]
+    bench_cycles: Option<u64>,
+
+    /// Initial window size as a multiple of the 64x32 display
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    scale: u32,
+
+    /// Scale factor for screenshots taken with F2
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    screenshot_scale: u32,
+
+    /// Joystick device of the gamepad. The emulator runs without it if it can't be opened.
+    #[cfg(feature = "gamepad")]
+    #[arg(long, value_name = "PATH", default_value = "/dev/input/js0")]
+    gamepad: PathBuf,
+
+    /// CHIP-8 keys for the d-pad up, down, left and right and the A, B, X and Y buttons,
+    /// as 8 hex digits with `-` for unmapped controls. By default the d-pad maps to 2/8/4/6,
+    /// which most games use for movement, and A to 5.
+    #[cfg(feature = "gamepad")]
+    #[arg(long, value_name = "MAP", default_value = "28465---", value_parser = gamepad::parse_gamepad_map)]
+    gamepad_map: gamepad::GamepadMap,
+}
+
+/// Installs a trace hook printing each instruction's address, raw value and disassembly to
+/// stderr.
+///
+/// Formatting happens in the hook, but writing is done by a separate thread fed through a
+/// bounded channel. When the channel is full lines are dropped instead of stalling emulation.
+fn install_trace(chip8: &mut Chip8) {
+    let (sender, receiver) = mpsc::sync_channel::<String>(TRACE_BUFFER_LINES);
+    std::thread::spawn(move || {
+        let mut stderr = std::io::stderr().lock();
+        for line in receiver {
+            // Write errors are ignored so tracing never interrupts execution
+            let _ = writeln!(stderr, "{line}");
+        }
+    });
+
+    let mut dropped = 0;
+    chip8.set_trace(Some(Box::new(move |chip8, raw, opcode| {
+        if dropped > 0
+            && sender
+                .try_send(format!("... {dropped} instructions not traced"))
+                .is_ok()
+        {
+            dropped = 0;
+        }
+
+        let line = format!("{:03X}: {raw:04X} - {opcode}", chip8.pc());
+        if sender.try_send(line).is_err() {
+            dropped += 1;
+        }
+    })));
+}
+
+/// Parses a color in `RRGGBB` or `#RRGGBB` hex format.
+fn parse_rgb(s: &str) -> Result<Rgb, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid color '{s}', expected RRGGBB hex"));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    Ok([channel(0), channel(2), channel(4)])
+}
+
+/// Converts an sRGB color to the linear color pixels fills the border around the display with.
+fn clear_color(rgb: Rgb) -> wgpu::Color {
+    let linear = |channel: u8| {
+        let c = f64::from(channel) / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    wgpu::Color {
+        r: linear(rgb[0]),
+        g: linear(rgb[1]),
+        b: linear(rgb[2]),
+        a: 1.0,
+    }
+}
+
+/// Parses a keypad mapping of 16 distinct letter or digit keys.
+fn parse_keymap(s: &str) -> Result<[KeyCode; 16], String> {
+    let keys = s
+        .chars()
+        .map(|c| {
+            Ok(match c.to_ascii_lowercase() {
+                'a' => KeyCode::KeyA,
+                'b' => KeyCode::KeyB,
+                'c' => KeyCode::KeyC,
+                'd' => KeyCode::KeyD,
+                'e' => KeyCode::KeyE,
+                'f' => KeyCode::KeyF,
+                'g' => KeyCode::KeyG,
+                'h' => KeyCode::KeyH,
+                'i' => KeyCode::KeyI,
+                'j' => KeyCode::KeyJ,
+                'k' => KeyCode::KeyK,
+                'l' => KeyCode::KeyL,
+                'm' => KeyCode::KeyM,
+                'n' => KeyCode::KeyN,
+                'o' => KeyCode::KeyO,
+                'p' => KeyCode::KeyP,
+                'q' => KeyCode::KeyQ,
+                'r' => KeyCode::KeyR,
+                's' => KeyCode::KeyS,
+                't' => KeyCode::KeyT,
+                'u' => KeyCode::KeyU,
+                'v' => KeyCode::KeyV,
+                'w' => KeyCode::KeyW,
+                'x' => KeyCode::KeyX,
+                'y' => KeyCode::KeyY,
+                'z' => KeyCode::KeyZ,
+                '0' => KeyCode::Digit0,
+                '1' => KeyCode::Digit1,
+                '2' => KeyCode::Digit2,
+                '3' => KeyCode::Digit3,
+                '4' => KeyCode::Digit4,
+                '5' => KeyCode::Digit5,
+                '6' => KeyCode::Digit6,
+                '7' => KeyCode::Digit7,
+                '8' => KeyCode::Digit8,
+                '9' => KeyCode::Digit9,
+                _ => return Err(format!("unsupported key '{c}', expected a letter or digit")),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let key_map: [KeyCode; 16] = keys
+        .try_into()
+        .map_err(|keys: Vec<_>| format!("expected 16 keys, got {}", keys.len()))?;
+
+    if (1..16).any(|i| key_map[..i].contains(&key_map[i])) {
+        return Err("keys must be distinct".to_string());
+    }
+
+    if let Some(c) = s
+        .chars()
+        .zip(key_map)
+        .find_map(|(c, key)| HOTKEY_KEYS.contains(&key).then_some(c))
+    {
+        return Err(format!("key '{c}' is reserved for a hotkey"));
+    }
+
+    Ok(key_map)
+}
+
+/// Encodes an 8-bit RGB image as a PNG.
+///
+/// The image data is stored uncompressed, which keeps the encoder tiny
+/// and is fine for the small images produced by screenshots.
+fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    // Each scanline is prefixed with its filter type (0 = none)
+    let scanlines: Vec<u8> = rgb
+        .chunks_exact(width as usize * 3)
+        .flat_map(|row| std::iter::once(0).chain(row.iter().copied()))
+        .collect();
+
+    // Wrap the scanlines in a zlib stream made of stored (uncompressed) deflate blocks
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = scanlines.chunks(0xFFFF).peekable();
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        zlib.push(u8::from(blocks.peek().is_none())); // Final block flag
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&scanlines).to_be_bytes());
+
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit depth, RGB color type
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, data) in [(b"IHDR", ihdr), (b"IDAT", zlib), (b"IEND", Vec::new())] {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let chunk_start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(&data);
+        let crc = crc32(&png[chunk_start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+
+    png
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Reads the ROM file at `path` into a new machine.
+fn load_rom(path: &Path) -> anyhow::Result<Chip8> {
+    let rom = std::fs::read(path).context("Failed to read ROM")?;
+    let mut chip8 = Chip8::default();
+    chip8.load(&rom).context("Failed to load ROM")?;
+    Ok(chip8)
+}
+
+/// Prints every instruction of the ROM as `ADDR: WORD  MNEMONIC`.
+fn print_disassembly(rom_path: &Path) -> anyhow::Result<()> {
+    let chip8 = load_rom(rom_path)?;
+
+    for (addr, word, opcode) in
+        chip8.disassemble_range(chip8.start_address(), chip8.rom_len() as u16)
+    {
+        println!("{addr:03X}: {word:04X}  {opcode}");
+    }
+
+    // A trailing odd byte can't form an instruction, print it as data
+    if chip8.rom_len() % 2 == 1 {
+        let addr = chip8.start_address() + chip8.rom_len() as u16 - 1;
+        let byte = chip8.mem_get(addr)?;
+        println!("{addr:03X}: {byte:02X}    DB {byte:02X}");
+    }
+
+    Ok(())
+}
+
+/// Runs `cycles` CPU cycles of the ROM as fast as possible and prints the throughput.
+///
+/// Only the CPU core is measured: nothing is rendered, no audio is played and the timers
+/// don't run. The random number generator is seeded so runs are repeatable.
+fn run_benchmark(rom_path: &Path, cycles: u64) -> anyhow::Result<()> {
+    let mut chip8 = load_rom(rom_path)?;
+    chip8.set_seed(0);
+
+    let start = Instant::now();
+    for _ in 0..cycles {
+        chip8.cpu_cycle().context("Chip8 Execution error")?;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!(
+        "{cycles} cycles in {elapsed:.3}s: {:.0} cycles/s ({:.0}x the default clock speed)",
+        cycles as f64 / elapsed,
+        cycles as f64 / elapsed / f64::from(DEFAULT_CPU_HZ)
+    );
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    let rom = std::fs::read(&args.rom_path).context("Failed to read ROM file")?;
+    if args.disasm {
+        return print_disassembly(&args.rom_path);
+    }
+
+    if let Some(cycles) = args.bench_cycles {
+        return run_benchmark(&args.rom_path, cycles);
+    }
 
     let event_loop = EventLoop::new().context("Failed to create event loop")?;
     event_loop.set_control_flow(ControlFlow::Poll);
 
-    let mut app = App::new(&rom).context("Failed to initialize application")?;
+    let mut app = App::new(&args).context("Failed to initialize application")?;
     event_loop
         .run_app(&mut app)
         .context("Error occurred during event loop execution")?;
 
+    if let Some(path) = &args.dump_state {
+        std::fs::write(path, app.runner.chip8_ref().dump_state())
+            .context("Failed to write machine state")?;
+    }
+
     // Return the result captured during the event loop
     app.exit_result
 }
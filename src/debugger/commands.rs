@@ -1,6 +1,9 @@
 use clap::{Args, Parser, Subcommand};
-use clap_num::{maybe_hex, maybe_hex_range};
+use clap_num::maybe_hex;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
 
+use super::{Condition, Expr};
 use crate::emu::Opcode;
 use crate::{u4, u12};
 
@@ -18,6 +21,14 @@ pub enum Command {
     #[command(visible_alias = "r")]
     Run,
 
+    /// Run until an address is reached, without leaving a breakpoint behind
+    #[command(visible_alias = "rt")]
+    RunTo {
+        /// The address
+        #[arg(value_parser = Expr::parse_u12)]
+        addr: Expr,
+    },
+
     /// Pause execution
     #[command(visible_alias = "p")]
     Pause,
@@ -30,6 +41,21 @@ pub enum Command {
     #[command(visible_alias = "q")]
     Quit,
 
+    /// Clear the output
+    #[command(visible_alias = "cls")]
+    Clear,
+
+    /// Load a new ROM and restart from its beginning
+    #[command(visible_alias = "lr")]
+    LoadRom {
+        /// Path to the CHIP-8 ROM file
+        path: PathBuf,
+
+        /// Keep the current breakpoints instead of clearing them
+        #[arg(long)]
+        keep_breakpoints: bool,
+    },
+
     /// Manage breakpoints
     #[command(visible_alias = "b")]
     Breakpoint {
@@ -51,6 +77,30 @@ pub enum Command {
         args: MemArgs,
     },
 
+    /// Search memory for a sequence of bytes
+    #[command(visible_alias = "f")]
+    Find {
+        /// The values to search for, in order. Values above FF are searched as 16-bit big-endian words
+        #[arg(required = true, value_parser = maybe_hex::<u16>)]
+        values: Vec<u16>,
+    },
+
+    /// List instructions in the ROM that can't be decoded
+    #[command(visible_alias = "l")]
+    Lint,
+
+    /// Show the font sprite of a hex digit
+    #[command(visible_alias = "fo")]
+    Font {
+        /// The digit (0-F)
+        #[arg(value_parser = u4_parse)]
+        digit: u4,
+    },
+
+    /// Show all registers and the stack
+    #[command(visible_alias = "reg")]
+    Regs,
+
     /// Set a V register value
     #[command(visible_alias = "v")]
     SetV {
@@ -59,24 +109,24 @@ pub enum Command {
         idx: u4,
 
         /// The value
-        #[arg(value_parser = maybe_hex::<u8>)]
-        value: u8,
+        #[arg(value_parser = Expr::parse_u8)]
+        value: Expr,
     },
 
     /// Set the I register
     #[command(visible_alias = "i")]
     SetI {
         /// The value
-        #[arg(value_parser = u12_parse)]
-        value: u12,
+        #[arg(value_parser = Expr::parse_u12)]
+        value: Expr,
     },
 
     /// Set the program counter
     #[command(visible_alias = "pc")]
     SetPc {
         /// The value
-        #[arg(value_parser = u12_parse)]
-        value: u12,
+        #[arg(value_parser = Expr::parse_u12)]
+        value: Expr,
     },
 
     /// Set key state
@@ -95,46 +145,115 @@ pub enum Command {
     #[command(visible_alias = "dt")]
     SetDt {
         /// The value
-        #[arg(value_parser = maybe_hex::<u8>)]
-        value: u8,
+        #[arg(value_parser = Expr::parse_u8)]
+        value: Expr,
     },
 
     /// Set sound timer
     #[command(visible_alias = "st")]
     SetSt {
         /// The value
-        #[arg(value_parser = maybe_hex::<u8>)]
-        value: u8,
+        #[arg(value_parser = Expr::parse_u8)]
+        value: Expr,
     },
 
     /// Push value onto the stack
     #[command(visible_alias = "pu")]
     Push {
         /// The value
-        #[arg(value_parser = u12_parse)]
-        value: u12,
+        #[arg(value_parser = Expr::parse_u12)]
+        value: Expr,
     },
 
     /// Pop value from the stack
     #[command(visible_alias = "po")]
     Pop,
+
+    /// Stop at unknown opcodes with PC pointing at them, so they can be inspected and patched
+    #[command(visible_alias = "bu")]
+    BreakOnUnknown {
+        /// The value (true/false)
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+
+    /// Stop the delay and sound timers from counting down while running
+    #[command(visible_alias = "ft")]
+    FreezeTimers {
+        /// The value (true/false)
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+
+    /// Show the most executed instructions, the first use starts counting them
+    #[command(visible_alias = "pr")]
+    Profile {
+        /// Clear the counts instead
+        #[arg(long)]
+        reset: bool,
+    },
+
+    /// Write executed instructions to a file, or stop tracing if no path is given
+    #[command(visible_alias = "t")]
+    Trace {
+        /// The file to write the trace to
+        path: Option<PathBuf>,
+
+        /// Include register values in each line
+        #[arg(long)]
+        regs: bool,
+    },
 }
 
 /// Result of a debugger command execution
 pub enum CommandResult {
     /// Command executed successfully
     Ok,
-    Breakpoints(Vec<u12>),
+    /// List of breakpoints (address, optional condition)
+    Breakpoints(Vec<(u12, Option<Condition>)>),
     MemDump {
         data: Vec<u8>,
         /// The starting offset
         offset: u12,
     },
     Disasm {
-        /// List of instructions (value, decoded opcode)
-        instructions: Vec<(u16, Opcode)>,
-        offset: u12,
+        /// List of instructions (address, value, decoded opcode)
+        instructions: Vec<(u16, u16, Opcode)>,
+        /// Addresses of instructions reachable from the start address, the rest is likely data
+        code: BTreeSet<u16>,
+    },
+    /// The register file and call stack
+    Registers {
+        v: [u8; 16],
+        i: u12,
+        pc: u12,
+        dt: u8,
+        st: u8,
+        stack: Vec<u12>,
     },
+    /// Instruction names and execution counts, most executed first
+    Profile {
+        counts: Vec<(&'static str, u64)>,
+    },
+    /// Addresses where a searched sequence starts
+    FindResult {
+        addresses: Vec<u16>,
+    },
+    /// A font sprite and the address it is stored at
+    Glyph {
+        address: u12,
+        data: Vec<u8>,
+    },
+    /// Address and raw value of each unknown instruction in the ROM
+    LintResult {
+        issues: Vec<(u16, u16)>,
+    },
+    /// Execution stopped at an unknown instruction, PC still points at it
+    UnknownOpcode {
+        address: u12,
+    },
+    /// The output should be cleared
+    Clear,
     Quit,
 }
 
@@ -144,16 +263,29 @@ pub enum BreakpointAction {
     #[command(visible_alias = "s")]
     Set {
         /// The address
-        #[arg(value_parser = u12_parse)]
-        addr: u12,
+        #[arg(value_parser = Expr::parse_u12)]
+        addr: Expr,
+    },
+
+    /// Set a breakpoint at an address that only triggers when a condition holds
+    #[command(visible_alias = "si")]
+    SetIf {
+        /// The address
+        #[arg(value_parser = Expr::parse_u12)]
+        addr: Expr,
+
+        /// The condition, e.g. V5==FF or I>=300. Values are in hex.
+        /// Registers: V0-VF, I, PC, DT, ST. Comparisons: ==, !=, <, >, <=, >=
+        #[arg(value_parser = Condition::parse)]
+        condition: Condition,
     },
 
     /// Clear a breakpoint at an address
     #[command(visible_alias = "c")]
     Clear {
         /// The address
-        #[arg(value_parser = u12_parse)]
-        addr: u12,
+        #[arg(value_parser = Expr::parse_u12)]
+        addr: Expr,
     },
 
     /// List all breakpoints
@@ -168,18 +300,19 @@ pub enum BreakpointAction {
 #[derive(Args, Clone)]
 pub struct MemArgs {
     /// Starting memory address
-    #[arg(value_parser = u12_parse)]
-    pub offset: u12,
+    #[arg(value_parser = Expr::parse_u12)]
+    pub offset: Expr,
 
     /// Number of bytes to display
-    #[arg(default_value = "32", value_parser = u12_parse)]
-    pub len: u12,
+    #[arg(default_value = "32", value_parser = Expr::parse_u12)]
+    pub len: Expr,
 }
 
-fn u12_parse(s: &str) -> Result<u12, String> {
-    maybe_hex_range(s, 0, 0xFFF).map(u12::new)
+/// Parses a 12-bit address, given in decimal or hex with a 0x prefix.
+pub fn u12_parse(s: &str) -> Result<u12, String> {
+    maybe_hex::<u16>(s).and_then(|value| u12::try_from(value).map_err(|e| e.to_string()))
 }
 
 fn u4_parse(s: &str) -> Result<u4, String> {
-    maybe_hex_range(s, 0, 0xF).map(u4::new)
+    maybe_hex::<u8>(s).and_then(|value| u4::try_from(value).map_err(|e| e.to_string()))
 }
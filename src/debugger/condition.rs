@@ -0,0 +1,184 @@
+use std::fmt;
+
+use crate::emu::Chip8;
+use crate::u4;
+
+/// A value read from the machine state when evaluating a condition.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    /// A V register
+    V(u4),
+    /// The index register
+    I,
+    /// The program counter
+    Pc,
+    /// The delay timer
+    Dt,
+    /// The sound timer
+    St,
+}
+
+impl Operand {
+    /// Parses a register name: `V0`-`VF`, `I`, `PC`, `DT` or `ST`, in any case.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let operand = match s.to_ascii_uppercase().as_str() {
+            "I" => Operand::I,
+            "PC" => Operand::Pc,
+            "DT" => Operand::Dt,
+            "ST" => Operand::St,
+            reg => {
+                let idx = reg
+                    .strip_prefix('V')
+                    .filter(|idx| idx.len() == 1)
+                    .and_then(|idx| u8::from_str_radix(idx, 16).ok())
+                    .ok_or(format!("unknown register '{s}'"))?;
+                Operand::V(u4::new(idx))
+            }
+        };
+
+        Ok(operand)
+    }
+
+    /// Reads the current value of the operand.
+    pub fn read(&self, chip8: &Chip8) -> u16 {
+        match self {
+            Operand::V(idx) => u16::from(chip8.v[*idx]),
+            Operand::I => usize::from(chip8.i) as u16,
+            Operand::Pc => usize::from(chip8.pc) as u16,
+            Operand::Dt => u16::from(chip8.delay_timer),
+            Operand::St => u16::from(chip8.sound_timer),
+        }
+    }
+}
+
+/// A comparison operator.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+/// A condition comparing a register against a constant, e.g. `V5==FF`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub operand: Operand,
+    pub comparison: Comparison,
+    pub value: u16,
+}
+
+impl Condition {
+    /// Parses a condition in the form `<register><comparison><hex value>`.
+    ///
+    /// Registers are `V0`-`VF`, `I`, `PC`, `DT` and `ST`.
+    /// Comparisons are `==`, `!=`, `<`, `>`, `<=` and `>=`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        // Two character operators have to be matched first
+        const OPERATORS: [(&str, Comparison); 6] = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ];
+
+        let (lhs, comparison, rhs) = OPERATORS
+            .iter()
+            .find_map(|(op, comparison)| {
+                s.split_once(op)
+                    .map(|(lhs, rhs)| (lhs.trim(), *comparison, rhs.trim()))
+            })
+            .ok_or("expected a comparison (==, !=, <, >, <=, >=)")?;
+
+        let operand = Operand::parse(lhs)?;
+
+        let rhs = rhs
+            .strip_prefix("0x")
+            .or_else(|| rhs.strip_prefix("0X"))
+            .unwrap_or(rhs);
+        let value = u16::from_str_radix(rhs, 16).map_err(|_| format!("invalid value '{rhs}'"))?;
+
+        Ok(Self {
+            operand,
+            comparison,
+            value,
+        })
+    }
+
+    /// Returns true if the condition holds for the current machine state.
+    pub fn eval(&self, chip8: &Chip8) -> bool {
+        let lhs = self.operand.read(chip8);
+
+        match self.comparison {
+            Comparison::Eq => lhs == self.value,
+            Comparison::Ne => lhs != self.value,
+            Comparison::Lt => lhs < self.value,
+            Comparison::Gt => lhs > self.value,
+            Comparison::Le => lhs <= self.value,
+            Comparison::Ge => lhs >= self.value,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.operand {
+            Operand::V(idx) => write!(f, "V{idx:X}")?,
+            Operand::I => write!(f, "I")?,
+            Operand::Pc => write!(f, "PC")?,
+            Operand::Dt => write!(f, "DT")?,
+            Operand::St => write!(f, "ST")?,
+        }
+
+        let op = match self.comparison {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Gt => ">",
+            Comparison::Le => "<=",
+            Comparison::Ge => ">=",
+        };
+
+        write!(f, "{op}{:X}", self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::u12;
+
+    #[test]
+    fn conditions_round_trip() {
+        for text in ["V5==FF", "I>=300", "PC!=200", "DT<10"] {
+            let condition = Condition::parse(text).unwrap();
+            assert_eq!(condition.to_string(), text);
+        }
+
+        let condition = Condition::parse("v5 == 0xff").unwrap();
+        assert!(condition.operand == Operand::V(u4::new(5)));
+        assert!(condition.comparison == Comparison::Eq);
+        assert_eq!(condition.value, 0xFF);
+    }
+
+    #[test]
+    fn conditions_read_the_machine_state() {
+        let mut chip8 = Chip8::new();
+        let v5_is_ff = Condition::parse("V5==FF").unwrap();
+        let i_from_300 = Condition::parse("I>=300").unwrap();
+        assert!(!v5_is_ff.eval(&chip8));
+        assert!(!i_from_300.eval(&chip8));
+
+        chip8.set_reg(u4::new(5), 0xFF);
+        chip8.set_index(u12::new(0x300));
+        assert!(v5_is_ff.eval(&chip8));
+        assert!(i_from_300.eval(&chip8));
+
+        chip8.set_index(u12::new(0x2FF));
+        assert!(!i_from_300.eval(&chip8));
+    }
+}
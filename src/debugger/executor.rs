@@ -1,23 +1,58 @@
 use super::commands::{BreakpointAction, Command, CommandResult};
+use super::{Condition, Expr, Operand};
 use crate::{
-    emu::{Chip8Error, Chip8Runner, Chip8RunnerResult, Display, MEMORY_SIZE, Opcode},
-    u12,
+    emu::{
+        Breakpoints, Chip8, Chip8Error, Chip8Runner, Chip8RunnerResult, Display, FONT_CHAR_SIZE,
+        MEMORY_SIZE, font_char_address,
+    },
+    u4, u12,
+};
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    rc::Rc,
 };
-use std::collections::HashSet;
 
 /// Executes debugger commands and manages the CHIP-8 runner state.
 pub struct Executor {
-    is_running: bool,
     runner: Chip8Runner,
-    breakpoints: HashSet<u12>,
+    /// Breakpoint addresses, each with an optional condition
+    breakpoints: HashMap<u12, Option<Condition>>,
+    /// Trace file shared with the trace hook installed in the `Chip8`
+    trace_writer: Option<Rc<RefCell<BufWriter<File>>>>,
+    /// Leave PC pointing at unknown opcodes instead of past them
+    break_on_unknown: bool,
+    /// One-shot breakpoint, cleared as soon as execution pauses
+    run_to: Option<u12>,
+    /// Machine state before the last step or run, used to report what changed
+    snapshot: Option<Snapshot>,
+    /// Addresses reachable from the start of the loaded ROM, traced on the first disassembly
+    reachable_code: Option<BTreeSet<u16>>,
+}
+
+/// The parts of the machine state compared between steps.
+struct Snapshot {
+    v: [u8; 16],
+    i: u12,
+    pc: u12,
+    memory: Box<[u8; MEMORY_SIZE]>,
 }
 
 impl Executor {
-    pub fn new(runner: Chip8Runner) -> Self {
+    pub fn new(mut runner: Chip8Runner) -> Self {
+        runner.pause();
+
         Self {
-            is_running: false,
             runner,
-            breakpoints: HashSet::new(),
+            breakpoints: HashMap::new(),
+            trace_writer: None,
+            break_on_unknown: false,
+            run_to: None,
+            snapshot: None,
+            reachable_code: None,
         }
     }
 
@@ -26,18 +61,21 @@ impl Executor {
     /// This should be called in the main loop to advance the emulation
     /// when the debugger is in the "running" state.
     pub fn poll(&mut self, dt: f32) -> Result<Chip8RunnerResult, Chip8Error> {
-        if !self.is_running {
-            return Ok(Chip8RunnerResult::Ok);
+        let breakpoints = ActiveBreakpoints {
+            breakpoints: &self.breakpoints,
+            run_to: self.run_to,
+        };
+        let result = self.runner.update_with_breakpoints(dt, Some(&breakpoints));
+        if self.stop_at_unknown(&result) {
+            // The runner doesn't report the cycles that ran before the error
+            self.pause();
+            return Ok(Chip8RunnerResult::HitBreakpoint { cycles: 0 });
         }
 
-        let result = self
-            .runner
-            .update_with_breakpoints(dt, Some(&self.breakpoints));
-
         // If a breakpoint is hit or an error occurs, execution is paused.
         // This allows the debugger to stop exactly when a condition is met.
-        if matches!(result, Err(_) | Ok(Chip8RunnerResult::HitBreakpoint)) {
-            self.is_running = false;
+        if matches!(result, Err(_) | Ok(Chip8RunnerResult::HitBreakpoint { .. })) {
+            self.pause();
         }
 
         result
@@ -46,47 +84,207 @@ impl Executor {
     pub fn execute(&mut self, command: Command) -> Result<CommandResult, Chip8Error> {
         match command {
             Command::Run => self.run(),
+            Command::RunTo { addr } => {
+                self.run_to = Some(self.eval_u12(addr));
+                self.run();
+            }
             Command::Pause => self.pause(),
             Command::Step => return self.step(),
-            Command::Quit => return Ok(CommandResult::Quit),
+            Command::Quit => {
+                self.flush_trace();
+                return Ok(CommandResult::Quit);
+            }
+            Command::Clear => return Ok(CommandResult::Clear),
+            Command::LoadRom {
+                path,
+                keep_breakpoints,
+            } => self.load_rom(&path, keep_breakpoints)?,
             Command::Breakpoint { action } => return Ok(self.handle_breakpoint(action)),
-            Command::Mem { args } => return Ok(self.handle_mem(args.offset, args.len)),
-            Command::Disasm { args } => return Ok(self.handle_disasm(args.offset, args.len)),
-            Command::SetV { idx, value } => self.runner.chip8_mut().v[idx] = value,
-            Command::SetI { value } => self.runner.chip8_mut().i = value,
-            Command::SetPc { value } => self.runner.chip8_mut().pc = value,
-            Command::SetKey { key, pressed } => self.runner.chip8_mut().keypad[key] = pressed,
-            Command::SetDt { value } => self.runner.chip8_mut().delay_timer = value,
-            Command::SetSt { value } => self.runner.chip8_mut().sound_timer = value,
-            Command::Push { value } => self.runner.chip8_mut().stack.push(value),
+            Command::Mem { args } => {
+                let (offset, len) = (self.eval_u12(args.offset), self.eval_u12(args.len));
+                return Ok(self.handle_mem(offset, len));
+            }
+            Command::Disasm { args } => {
+                let (offset, len) = (self.eval_u12(args.offset), self.eval_u12(args.len));
+                return Ok(self.handle_disasm(offset, len));
+            }
+            Command::Find { values } => return Ok(self.handle_find(&values)),
+            Command::Font { digit } => {
+                // Read the sprite from memory, so a custom font set is shown as loaded
+                let address = font_char_address(digit);
+                let start = usize::from(address);
+                return Ok(CommandResult::Glyph {
+                    address,
+                    data: self.runner.chip8_ref().memory()[start..start + FONT_CHAR_SIZE].to_vec(),
+                });
+            }
+            Command::Lint => {
+                return Ok(CommandResult::LintResult {
+                    issues: self.runner.chip8_ref().lint_rom(),
+                });
+            }
+            Command::Regs => {
+                return Ok(CommandResult::Registers {
+                    v: *self.get_v(),
+                    i: self.get_i(),
+                    pc: self.get_pc(),
+                    dt: self.get_delay_timer(),
+                    st: self.get_sound_timer(),
+                    stack: self.get_stack().to_vec(),
+                });
+            }
+            Command::SetV { idx, value } => {
+                let value = self.eval_u8(value);
+                self.runner.chip8_mut().set_reg(idx, value);
+            }
+            Command::SetI { value } => {
+                let value = self.eval_u12(value);
+                self.runner.chip8_mut().set_index(value);
+            }
+            Command::SetPc { value } => {
+                let value = self.eval_u12(value);
+                self.runner.chip8_mut().set_pc(value);
+            }
+            Command::SetKey { key, pressed } => self.runner.chip8_mut().set_key(key, pressed),
+            Command::SetDt { value } => {
+                let value = self.eval_u8(value);
+                self.runner.chip8_mut().delay_timer = value;
+            }
+            Command::SetSt { value } => {
+                let value = self.eval_u8(value);
+                self.runner.chip8_mut().sound_timer = value;
+            }
+            Command::Push { value } => {
+                let value = self.eval_u12(value);
+                self.runner.chip8_mut().stack_push(value)?;
+            }
             Command::Pop => {
-                self.runner.chip8_mut().stack.pop();
+                self.runner.chip8_mut().stack_pop();
             }
+            Command::BreakOnUnknown { enabled } => self.break_on_unknown = enabled,
+            Command::FreezeTimers { enabled } => self.runner.freeze_timers(enabled),
+            Command::Profile { reset } => {
+                // Counting only starts when first asked for, so it costs nothing until then
+                if !reset && self.runner.chip8_ref().opcode_profile().is_some() {
+                    return Ok(self.handle_profile());
+                }
+                self.runner.chip8_mut().enable_profiling();
+            }
+            Command::Trace { path, regs } => match path {
+                Some(path) => self.start_trace(&path, regs)?,
+                None => self.stop_trace(),
+            },
         };
 
         Ok(CommandResult::Ok)
     }
 
     pub fn run(&mut self) {
-        self.is_running = true;
+        self.take_snapshot();
+        self.runner.resume();
     }
 
     pub fn pause(&mut self) {
-        self.is_running = false;
+        self.runner.pause();
+        self.run_to = None;
+        self.flush_trace();
     }
 
     /// Execute a single instruction.
+    ///
+    /// Only the CPU is cycled, the timers never advance between manual steps.
+    /// They only count down while running, and not at all if frozen with `Chip8Runner::freeze_timers`.
     pub fn step(&mut self) -> Result<CommandResult, Chip8Error> {
-        self.runner.chip8_mut().cpu_cycle()?;
+        self.take_snapshot();
+        let result = self.runner.chip8_mut().cpu_cycle();
+        if self.stop_at_unknown(&result) {
+            return Ok(CommandResult::UnknownOpcode {
+                address: self.get_pc(),
+            });
+        }
+        result?;
         Ok(CommandResult::Ok)
     }
 
+    /// If breaking on unknown opcodes is enabled and `result` is an unknown opcode error,
+    /// moves PC back so it points at the offending instruction and returns true.
+    ///
+    /// PC is incremented before an instruction is executed, so without this it would point
+    /// at the instruction after the unknown one.
+    fn stop_at_unknown<T>(&mut self, result: &Result<T, Chip8Error>) -> bool {
+        if !self.break_on_unknown
+            || !matches!(
+                result,
+                Err(Chip8Error::UnknownOpcode { .. } | Chip8Error::UnknownALUOpcode { .. })
+            )
+        {
+            return false;
+        }
+
+        let chip8 = self.runner.chip8_mut();
+        chip8.pc = chip8.pc.wrapping_sub(2);
+        true
+    }
+
+    fn take_snapshot(&mut self) {
+        let chip8 = self.runner.chip8_ref();
+        self.snapshot = Some(Snapshot {
+            v: chip8.v,
+            i: chip8.i,
+            pc: chip8.pc,
+            memory: Box::new(*chip8.memory()),
+        });
+    }
+
+    /// Get the registers that changed during the last step or run.
+    pub fn changed_registers(&self) -> Vec<Operand> {
+        let Some(snapshot) = &self.snapshot else {
+            return Vec::new();
+        };
+        let chip8 = self.runner.chip8_ref();
+
+        let mut changed: Vec<Operand> = (0..16)
+            .filter(|&idx| chip8.v[idx] != snapshot.v[idx])
+            .map(|idx| Operand::V(u4::new(idx as u8)))
+            .collect();
+        if chip8.i != snapshot.i {
+            changed.push(Operand::I);
+        }
+        if chip8.pc != snapshot.pc {
+            changed.push(Operand::Pc);
+        }
+
+        changed
+    }
+
+    /// Get the memory addresses written during the last step or run.
+    pub fn changed_memory(&self) -> Vec<u16> {
+        let Some(snapshot) = &self.snapshot else {
+            return Vec::new();
+        };
+
+        self.runner
+            .chip8_ref()
+            .memory()
+            .iter()
+            .zip(snapshot.memory.iter())
+            .enumerate()
+            .filter(|(_, (new, old))| new != old)
+            .map(|(addr, _)| addr as u16)
+            .collect()
+    }
+
     pub fn is_running(&self) -> bool {
-        self.is_running
+        !self.runner.is_paused()
+    }
+
+    pub fn get_display(&self) -> Display<bool> {
+        self.runner.chip8_ref().display()
     }
 
-    pub fn get_display(&self) -> &Display<bool> {
-        &self.runner.chip8_ref().display
+    /// Get the planes a pixel is lit in as a bitmask (bit 0 = first plane, bit 1 = second plane).
+    pub fn get_display_planes(&self, y: usize, x: usize) -> u8 {
+        self.runner.get_display_planes(y, x)
     }
 
     pub fn get_pc(&self) -> u12 {
@@ -101,41 +299,126 @@ impl Executor {
         &self.runner.chip8_ref().v
     }
 
-    pub fn get_stack(&self) -> &Vec<u12> {
-        &self.runner.chip8_ref().stack
+    pub fn get_stack(&self) -> &[u12] {
+        self.runner.chip8_ref().stack()
     }
 
     pub fn get_delay_timer(&self) -> u8 {
-        self.runner.chip8_ref().delay_timer
+        self.runner.delay_timer()
     }
 
     pub fn get_sound_timer(&self) -> u8 {
-        self.runner.chip8_ref().sound_timer
+        self.runner.sound_timer()
+    }
+
+    /// Iterates over the addresses of all breakpoints, in no particular order.
+    pub fn breakpoints(&self) -> impl Iterator<Item = u12> + '_ {
+        self.breakpoints.keys().copied()
+    }
+
+    /// Returns true if a breakpoint, conditional or not, is set at the address.
+    pub fn has_breakpoint(&self, addr: u12) -> bool {
+        self.breakpoints.contains_key(&addr)
     }
 
     pub fn get_keypad(&self) -> &[bool; 16] {
         &self.runner.chip8_ref().keypad
     }
 
+    pub fn runner(&self) -> &Chip8Runner {
+        &self.runner
+    }
+
     pub fn runner_mut(&mut self) -> &mut Chip8Runner {
         &mut self.runner
     }
 
+    /// Replace the loaded ROM and restart the machine, keeping quirks and the trace.
+    fn load_rom(&mut self, path: &Path, keep_breakpoints: bool) -> Result<(), Chip8Error> {
+        let rom = fs::read(path)?;
+        let chip8 = self.runner.chip8_mut();
+        chip8.load(&rom)?;
+        chip8.reset();
+        if chip8.opcode_profile().is_some() {
+            chip8.enable_profiling();
+        }
+
+        self.pause();
+        self.snapshot = None;
+        self.reachable_code = None;
+        if !keep_breakpoints {
+            self.breakpoints.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Start writing each executed instruction to the file at `path`.
+    fn start_trace(&mut self, path: &Path, regs: bool) -> Result<(), Chip8Error> {
+        let writer = Rc::new(RefCell::new(BufWriter::new(File::create(path)?)));
+        self.trace_writer = Some(writer.clone());
+
+        self.runner
+            .chip8_mut()
+            .set_trace(Some(Box::new(move |chip8, raw, opcode| {
+                let mut line = format!("{:03X}: {raw:04X} - {opcode}", chip8.pc);
+                if regs {
+                    for (idx, value) in chip8.v.iter().enumerate() {
+                        line += &format!(" V{idx:X}={value:02X}");
+                    }
+                    line += &format!(" I={:03X}", chip8.i);
+                }
+
+                // Write errors are ignored so tracing never interrupts execution
+                let _ = writeln!(writer.borrow_mut(), "{line}");
+            })));
+
+        Ok(())
+    }
+
+    /// Stop tracing and flush the trace file.
+    fn stop_trace(&mut self) {
+        self.flush_trace();
+        self.trace_writer = None;
+        self.runner.chip8_mut().set_trace(None);
+    }
+
+    fn flush_trace(&mut self) {
+        if let Some(writer) = &self.trace_writer {
+            let _ = writer.borrow_mut().flush();
+        }
+    }
+
+    /// Evaluates a 12-bit command argument against the current machine state.
+    fn eval_u12(&self, expr: Expr) -> u12 {
+        expr.eval_u12(self.runner.chip8_ref())
+    }
+
+    /// Evaluates an 8-bit command argument against the current machine state.
+    fn eval_u8(&self, expr: Expr) -> u8 {
+        expr.eval_u8(self.runner.chip8_ref())
+    }
+
     fn handle_breakpoint(&mut self, action: BreakpointAction) -> CommandResult {
         match action {
             BreakpointAction::Set { addr } => {
-                self.breakpoints.insert(addr);
+                self.breakpoints.insert(self.eval_u12(addr), None);
+            }
+            BreakpointAction::SetIf { addr, condition } => {
+                self.breakpoints
+                    .insert(self.eval_u12(addr), Some(condition));
             }
             BreakpointAction::Clear { addr } => {
-                self.breakpoints.remove(&addr);
+                self.breakpoints.remove(&self.eval_u12(addr));
             }
             BreakpointAction::ClearAll => {
                 self.breakpoints.clear();
             }
             BreakpointAction::List => {
                 return CommandResult::Breakpoints({
-                    let mut bps: Vec<u12> = self.breakpoints.iter().cloned().collect();
-                    bps.sort();
+                    let mut bps: Vec<(u12, Option<Condition>)> =
+                        self.breakpoints.iter().map(|(k, v)| (*k, *v)).collect();
+                    bps.sort_by_key(|(addr, _)| *addr);
                     bps
                 });
             }
@@ -146,29 +429,371 @@ impl Executor {
 
     fn handle_mem(&self, offset: u12, len: u12) -> CommandResult {
         let end = MEMORY_SIZE.min(usize::from(offset) + usize::from(len));
-        let data = self.runner.chip8_ref().memory[usize::from(offset)..end].to_vec();
+        let data = self.runner.chip8_ref().memory()[usize::from(offset)..end].to_vec();
 
         CommandResult::MemDump { data, offset }
     }
 
-    fn handle_disasm(&self, offset: u12, len: u12) -> CommandResult {
-        let end = MEMORY_SIZE.min(usize::from(offset) + usize::from(len));
-        let data = &self.runner.chip8_ref().memory[usize::from(offset)..end];
-
-        // Reads a range of memory and decodes it into instructions.
-        // CHIP-8 instructions are 2 bytes long, so we chunk the memory by 2.
-        let instructions = data
-            .chunks_exact(2)
-            .map(|chunk| {
-                let value = u16::from_be_bytes([chunk[0], chunk[1]]);
-                let opcode = Opcode::decode(value);
-                (value, opcode)
+    fn handle_profile(&self) -> CommandResult {
+        let mut counts: Vec<(&'static str, u64)> = self
+            .runner
+            .chip8_ref()
+            .opcode_profile()
+            .map(|profile| profile.iter().map(|(k, v)| (*k, *v)).collect())
+            .unwrap_or_default();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        CommandResult::Profile { counts }
+    }
+
+    fn handle_find(&self, values: &[u16]) -> CommandResult {
+        let pattern: Vec<u8> = values
+            .iter()
+            .flat_map(|&value| match u8::try_from(value) {
+                Ok(byte) => vec![byte],
+                Err(_) => value.to_be_bytes().to_vec(),
             })
             .collect();
 
+        let addresses = self
+            .runner
+            .chip8_ref()
+            .memory()
+            .windows(pattern.len())
+            .enumerate()
+            .filter(|(_, window)| *window == pattern.as_slice())
+            .map(|(addr, _)| addr as u16)
+            .collect();
+
+        CommandResult::FindResult { addresses }
+    }
+
+    fn handle_disasm(&mut self, offset: u12, len: u12) -> CommandResult {
+        let offset = usize::from(offset) as u16;
+        let len = usize::from(len) as u16;
+
+        let chip8 = self.runner.chip8_ref();
+        let code = self
+            .reachable_code
+            .get_or_insert_with(|| chip8.trace_reachable_code());
         CommandResult::Disasm {
-            instructions,
-            offset,
+            instructions: chip8.disassemble_range(offset, len),
+            code: code.clone(),
+        }
+    }
+}
+
+impl Breakpoints for HashMap<u12, Option<Condition>> {
+    fn should_break(&self, chip8: &Chip8) -> bool {
+        match self.get(&chip8.pc) {
+            Some(Some(condition)) => condition.eval(chip8),
+            Some(None) => true,
+            None => false,
         }
     }
 }
+
+/// The persistent breakpoints combined with the one-shot run-to address.
+struct ActiveBreakpoints<'a> {
+    breakpoints: &'a HashMap<u12, Option<Condition>>,
+    run_to: Option<u12>,
+}
+
+impl Breakpoints for ActiveBreakpoints<'_> {
+    fn should_break(&self, chip8: &Chip8) -> bool {
+        self.run_to == Some(chip8.pc) || self.breakpoints.should_break(chip8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debugger::MemArgs;
+
+    fn executor(rom: &[u8]) -> Executor {
+        let mut chip8 = Chip8::new();
+        chip8.load(rom).unwrap();
+        Executor::new(Chip8Runner::new(chip8))
+    }
+
+    #[test]
+    fn step_reports_changed_registers() {
+        // 7305: V3 += 5
+        let mut executor = executor(&[0x73, 0x05]);
+        executor.step().unwrap();
+
+        let changed = executor.changed_registers();
+        assert!(changed.contains(&Operand::V(u4::new(3))));
+        assert!(changed.contains(&Operand::Pc));
+        assert!(!changed.contains(&Operand::V(u4::new(0))));
+        assert!(!changed.contains(&Operand::I));
+    }
+
+    #[test]
+    fn step_leaves_timers_alone() {
+        // 1200: jump to self
+        let mut executor = executor(&[0x12, 0x00]);
+        executor
+            .execute(Command::FreezeTimers { enabled: true })
+            .unwrap();
+        executor.runner_mut().chip8_mut().delay_timer = 10;
+
+        for _ in 0..100 {
+            executor.step().unwrap();
+        }
+        assert_eq!(executor.get_delay_timer(), 10);
+    }
+
+    #[test]
+    fn unknown_opcode_stops_step_without_an_error() {
+        // 6001: V0 = 1, FFFF: unknown
+        let mut executor = executor(&[0x60, 0x01, 0xFF, 0xFF]);
+        executor
+            .execute(Command::BreakOnUnknown { enabled: true })
+            .unwrap();
+
+        executor.step().unwrap();
+        assert!(matches!(
+            executor.step(),
+            Ok(CommandResult::UnknownOpcode { address }) if address == u12::new(0x202)
+        ));
+        assert_eq!(executor.get_pc(), u12::new(0x202));
+    }
+
+    #[test]
+    fn unknown_opcode_pauses_without_an_error() {
+        // 6001: V0 = 1, FFFF: unknown
+        let mut executor = executor(&[0x60, 0x01, 0xFF, 0xFF]);
+        executor
+            .execute(Command::BreakOnUnknown { enabled: true })
+            .unwrap();
+        executor.run();
+        assert!(matches!(
+            executor.poll(0.1),
+            Ok(Chip8RunnerResult::HitBreakpoint { .. })
+        ));
+        assert!(!executor.is_running());
+        assert_eq!(executor.get_pc(), u12::new(0x202));
+    }
+
+    #[test]
+    fn profile_counts_after_it_is_started() {
+        // 7001: V0 += 1, 1200: jump to start
+        let mut executor = executor(&[0x70, 0x01, 0x12, 0x00]);
+        executor.step().unwrap();
+        assert!(executor.runner().chip8_ref().opcode_profile().is_none());
+
+        let profile = Command::Profile { reset: false };
+        assert!(matches!(
+            executor.execute(profile.clone()),
+            Ok(CommandResult::Ok)
+        ));
+        for _ in 0..5 {
+            executor.step().unwrap();
+        }
+
+        let Ok(CommandResult::Profile { counts }) = executor.execute(profile) else {
+            panic!("expected profile counts");
+        };
+        assert_eq!(counts, [("Jump", 3), ("AddRegImm", 2)]);
+    }
+
+    #[test]
+    fn disasm_trace_is_cached_until_a_rom_is_loaded() {
+        // 1204: jump over the data word, 1204: jump to self
+        let mut executor = executor(&[0x12, 0x04, 0xAB, 0xCD, 0x12, 0x04]);
+        let disasm = || Command::Disasm {
+            args: MemArgs {
+                offset: u12::new(0x200).into(),
+                len: u12::new(6).into(),
+            },
+        };
+
+        let Ok(CommandResult::Disasm { code, .. }) = executor.execute(disasm()) else {
+            panic!("expected a disassembly");
+        };
+        assert_eq!(code, BTreeSet::from([0x200, 0x204]));
+
+        // The trace is per ROM, so patching memory doesn't change it
+        executor
+            .runner_mut()
+            .chip8_mut()
+            .mem_set(0x202, 0x12)
+            .unwrap();
+        let Ok(CommandResult::Disasm { code, .. }) = executor.execute(disasm()) else {
+            panic!("expected a disassembly");
+        };
+        assert_eq!(code, BTreeSet::from([0x200, 0x204]));
+    }
+
+    #[test]
+    fn conditional_breakpoint_fires_when_the_condition_holds() {
+        // 7001: V0 += 1, 1200: jump back
+        let mut executor = executor(&[0x70, 0x01, 0x12, 0x00]);
+        executor
+            .execute(Command::Breakpoint {
+                action: BreakpointAction::SetIf {
+                    addr: u12::new(0x202).into(),
+                    condition: Condition::parse("V0==3").unwrap(),
+                },
+            })
+            .unwrap();
+
+        executor.run();
+        assert!(matches!(
+            executor.poll(0.1),
+            Ok(Chip8RunnerResult::HitBreakpoint { .. })
+        ));
+        assert_eq!(executor.get_pc(), u12::new(0x202));
+        assert_eq!(executor.get_v()[0], 3);
+    }
+
+    #[test]
+    fn run_to_fires_once() {
+        // 7001: V0 += 1, 1200: jump back
+        let mut executor = executor(&[0x70, 0x01, 0x12, 0x00]);
+        executor
+            .execute(Command::RunTo {
+                addr: u12::new(0x202).into(),
+            })
+            .unwrap();
+        assert!(matches!(
+            executor.poll(0.1),
+            Ok(Chip8RunnerResult::HitBreakpoint { .. })
+        ));
+        assert_eq!(executor.get_pc(), u12::new(0x202));
+        assert_eq!(executor.get_v()[0], 1);
+
+        // The address is passed again many times without stopping
+        executor.run();
+        assert!(matches!(
+            executor.poll(0.1),
+            Ok(Chip8RunnerResult::Ok { .. })
+        ));
+        assert!(executor.is_running());
+        assert!(executor.get_v()[0] > 2);
+        assert_eq!(executor.breakpoints().count(), 0);
+    }
+
+    #[test]
+    fn loading_a_rom_restarts_and_clears_breakpoints() {
+        let path = std::env::temp_dir().join("chip8_rust_executor_load.ch8");
+        // 6342: V3 = 42, 1202: jump to self
+        fs::write(&path, [0x63, 0x42, 0x12, 0x02]).unwrap();
+
+        // 7001: V0 += 1, 7101: V1 += 1
+        let mut executor = executor(&[0x70, 0x01, 0x71, 0x01]);
+        executor.step().unwrap();
+        executor
+            .execute(Command::Breakpoint {
+                action: BreakpointAction::Set {
+                    addr: u12::new(0x202).into(),
+                },
+            })
+            .unwrap();
+
+        executor
+            .execute(Command::LoadRom {
+                path: path.clone(),
+                keep_breakpoints: false,
+            })
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(executor.get_pc(), u12::new(0x200));
+        assert_eq!(executor.get_v()[0], 0);
+        assert_eq!(executor.breakpoints().count(), 0);
+        executor.step().unwrap();
+        assert_eq!(executor.get_v()[3], 0x42);
+    }
+
+    #[test]
+    fn find_matches_bytes_words_and_sequences() {
+        // A22A: I = 22A, 600C: V0 = 0C, A22A: I = 22A
+        let mut executor = executor(&[0xA2, 0x2A, 0x60, 0x0C, 0xA2, 0x2A]);
+        let mut find = |values: &[u16]| match executor.execute(Command::Find {
+            values: values.to_vec(),
+        }) {
+            Ok(CommandResult::FindResult { addresses }) => addresses,
+            _ => panic!("expected find results"),
+        };
+
+        assert_eq!(find(&[0x2A]), [0x201, 0x205]);
+        assert_eq!(find(&[0xA22A]), [0x200, 0x204]);
+        assert_eq!(find(&[0x2A, 0x600C]), [0x201]);
+        assert_eq!(find(&[0x0C, 0xA2, 0x2A]), [0x203]);
+    }
+
+    #[test]
+    fn regs_reports_the_register_file() {
+        // 6A12: VA = 12, A345: I = 345, 2208: call 208, 1206: jump to self
+        let mut executor = executor(&[0x6A, 0x12, 0xA3, 0x45, 0x22, 0x08, 0x12, 0x06]);
+        for _ in 0..3 {
+            executor.step().unwrap();
+        }
+        executor.runner_mut().chip8_mut().delay_timer = 7;
+
+        let Ok(CommandResult::Registers {
+            v,
+            i,
+            pc,
+            dt,
+            st,
+            stack,
+        }) = executor.execute(Command::Regs)
+        else {
+            panic!("expected registers");
+        };
+        assert_eq!(v[0xA], 0x12);
+        assert_eq!(i, u12::new(0x345));
+        assert_eq!(pc, u12::new(0x208));
+        assert_eq!((dt, st), (7, 0));
+        assert_eq!(stack, [u12::new(0x206)]);
+    }
+
+    #[test]
+    fn missing_rom_file_is_an_io_error() {
+        let mut executor = executor(&[0x12, 0x00]);
+        let result = executor.execute(Command::LoadRom {
+            path: std::env::temp_dir().join("chip8_rust_missing_rom.ch8"),
+            keep_breakpoints: false,
+        });
+
+        assert!(matches!(
+            result,
+            Err(Chip8Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+        // The loaded ROM is left as it was
+        assert_eq!(
+            executor.runner().chip8_ref().memory_range(0x200..0x202),
+            Some(&[0x12, 0x00][..])
+        );
+    }
+
+    #[test]
+    fn breakpoint_accessors_list_set_breakpoints() {
+        let mut executor = executor(&[0x12, 0x00]);
+        executor
+            .execute(Command::Breakpoint {
+                action: BreakpointAction::Set {
+                    addr: u12::new(0x204).into(),
+                },
+            })
+            .unwrap();
+        executor
+            .execute(Command::Breakpoint {
+                action: BreakpointAction::SetIf {
+                    addr: u12::new(0x200).into(),
+                    condition: Condition::parse("V0==1").unwrap(),
+                },
+            })
+            .unwrap();
+
+        let mut breakpoints: Vec<u12> = executor.breakpoints().collect();
+        breakpoints.sort();
+        assert_eq!(breakpoints, [u12::new(0x200), u12::new(0x204)]);
+        assert!(executor.has_breakpoint(u12::new(0x200)));
+        assert!(executor.has_breakpoint(u12::new(0x204)));
+        assert!(!executor.has_breakpoint(u12::new(0x202)));
+    }
+}
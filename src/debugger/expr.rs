@@ -0,0 +1,92 @@
+use clap_num::maybe_hex;
+
+use super::Operand;
+use crate::emu::Chip8;
+use crate::u12;
+
+/// A command argument that is either a constant or read from a register when the command runs.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Expr {
+    /// A constant value
+    Literal(u16),
+    /// The current value of a register
+    Register(Operand),
+}
+
+impl Expr {
+    /// Parses a 12-bit expression: an address in decimal or hex with a 0x prefix, or any register.
+    pub fn parse_u12(s: &str) -> Result<Self, String> {
+        match maybe_hex::<u16>(s) {
+            Ok(value) => u12::try_from(value)
+                .map(|_| Expr::Literal(value))
+                .map_err(|e| e.to_string()),
+            Err(_) => Operand::parse(s).map(Expr::Register),
+        }
+    }
+
+    /// Parses an 8-bit expression: a byte in decimal or hex with a 0x prefix, or an 8-bit
+    /// register (`V0`-`VF`, `DT` or `ST`).
+    pub fn parse_u8(s: &str) -> Result<Self, String> {
+        match maybe_hex::<u8>(s) {
+            Ok(value) => Ok(Expr::Literal(u16::from(value))),
+            Err(_) => match Operand::parse(s)? {
+                Operand::I | Operand::Pc => Err(format!("register '{s}' doesn't fit in a byte")),
+                operand => Ok(Expr::Register(operand)),
+            },
+        }
+    }
+
+    /// Evaluates the expression against the current machine state.
+    pub fn eval(&self, chip8: &Chip8) -> u16 {
+        match self {
+            Expr::Literal(value) => *value,
+            Expr::Register(operand) => operand.read(chip8),
+        }
+    }
+
+    /// Evaluates an expression parsed with `parse_u12`.
+    pub fn eval_u12(&self, chip8: &Chip8) -> u12 {
+        u12::new(self.eval(chip8))
+    }
+
+    /// Evaluates an expression parsed with `parse_u8`.
+    pub fn eval_u8(&self, chip8: &Chip8) -> u8 {
+        self.eval(chip8) as u8
+    }
+}
+
+impl From<u12> for Expr {
+    fn from(value: u12) -> Self {
+        Expr::Literal(usize::from(value) as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::u4;
+
+    #[test]
+    fn registers_are_read_when_evaluated() {
+        let mut chip8 = Chip8::new();
+        chip8.set_pc(u12::new(0x2A4));
+        chip8.set_index(u12::new(0x350));
+        chip8.set_reg(u4::new(5), 0x7F);
+
+        let pc = Expr::parse_u12("PC").unwrap();
+        let i = Expr::parse_u12("i").unwrap();
+        let v5 = Expr::parse_u8("V5").unwrap();
+        assert_eq!(pc.eval_u12(&chip8), u12::new(0x2A4));
+        assert_eq!(i.eval_u12(&chip8), u12::new(0x350));
+        assert_eq!(v5.eval_u8(&chip8), 0x7F);
+
+        chip8.set_reg(u4::new(5), 0x80);
+        assert_eq!(v5.eval_u8(&chip8), 0x80);
+        assert_eq!(
+            Expr::parse_u12("0x300").unwrap().eval_u12(&chip8),
+            u12::new(0x300)
+        );
+        assert!(Expr::parse_u8("PC").is_err());
+        assert!(Expr::parse_u12("0x1000").is_err());
+    }
+}
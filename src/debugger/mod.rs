@@ -1,5 +1,9 @@
 mod commands;
+mod condition;
 mod executor;
+mod expr;
 
 pub use commands::*;
+pub use condition::*;
 pub use executor::*;
+pub use expr::*;
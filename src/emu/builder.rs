@@ -0,0 +1,205 @@
+use super::{Chip8, Chip8Error, Chip8Runner, Quirks};
+
+/// Builder for a configured and loaded [`Chip8`].
+#[derive(Default)]
+pub struct Chip8Builder {
+    start_address: Option<u16>,
+    quirks: Quirks,
+    seed: Option<u64>,
+    max_stack_depth: Option<usize>,
+    rom: Option<Vec<u8>>,
+}
+
+impl Chip8Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the address the ROM is loaded at and execution starts from.
+    pub fn start_address(mut self, address: u16) -> Self {
+        self.start_address = Some(address);
+        self
+    }
+
+    /// Set the quirks used when executing instructions.
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Seed the random number generator used by the Cxnn instruction.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the maximum number of nested subroutine calls.
+    pub fn max_stack_depth(mut self, depth: usize) -> Self {
+        self.max_stack_depth = Some(depth);
+        self
+    }
+
+    /// Set the ROM loaded into memory on build.
+    pub fn rom(mut self, rom: &[u8]) -> Self {
+        self.rom = Some(rom.to_vec());
+        self
+    }
+
+    /// Build the `Chip8`, loading the ROM if one was set.
+    pub fn build(self) -> Result<Chip8, Chip8Error> {
+        let mut chip8 = match self.start_address {
+            Some(address) => Chip8::with_start_address(address)?,
+            None => Chip8::new(),
+        };
+        chip8.set_quirks(self.quirks);
+
+        if let Some(seed) = self.seed {
+            chip8.set_seed(seed);
+        }
+
+        if let Some(depth) = self.max_stack_depth {
+            chip8.set_max_stack_depth(depth);
+        }
+
+        if let Some(rom) = &self.rom {
+            chip8.load(rom)?;
+        }
+
+        Ok(chip8)
+    }
+}
+
+/// Builder for a [`Chip8Runner`] wrapping a configured [`Chip8`].
+#[derive(Default)]
+pub struct Chip8RunnerBuilder {
+    chip8: Chip8Builder,
+    cpu_hz: Option<f32>,
+    max_dt: Option<f32>,
+}
+
+impl Chip8RunnerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the address the ROM is loaded at and execution starts from.
+    pub fn start_address(mut self, address: u16) -> Self {
+        self.chip8 = self.chip8.start_address(address);
+        self
+    }
+
+    /// Set the quirks used when executing instructions.
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.chip8 = self.chip8.quirks(quirks);
+        self
+    }
+
+    /// Seed the random number generator used by the Cxnn instruction.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.chip8 = self.chip8.seed(seed);
+        self
+    }
+
+    /// Set the maximum number of nested subroutine calls.
+    pub fn max_stack_depth(mut self, depth: usize) -> Self {
+        self.chip8 = self.chip8.max_stack_depth(depth);
+        self
+    }
+
+    /// Set the ROM loaded into memory on build.
+    pub fn rom(mut self, rom: &[u8]) -> Self {
+        self.chip8 = self.chip8.rom(rom);
+        self
+    }
+
+    /// Set the CPU clock speed in instructions per second.
+    pub fn cpu_hz(mut self, hz: f32) -> Self {
+        self.cpu_hz = Some(hz);
+        self
+    }
+
+    /// Set the largest time step processed by a single update, in seconds.
+    pub fn max_dt(mut self, max_dt: f32) -> Self {
+        self.max_dt = Some(max_dt);
+        self
+    }
+
+    /// Build the `Chip8Runner`, loading the ROM if one was set.
+    ///
+    /// Fails if the CPU clock speed or the maximum time step isn't positive.
+    pub fn build(self) -> Result<Chip8Runner, Chip8Error> {
+        // Checked here, as the runner setters panic on invalid values
+        if let Some(hz) = self.cpu_hz
+            && !(hz > 0.0 && hz.is_finite())
+        {
+            return Err(Chip8Error::InvalidCpuHz { hz });
+        }
+        if let Some(max_dt) = self.max_dt
+            && (max_dt.is_nan() || max_dt <= 0.0)
+        {
+            return Err(Chip8Error::InvalidMaxDt { max_dt });
+        }
+
+        let mut runner = Chip8Runner::new(self.chip8.build()?);
+
+        if let Some(hz) = self.cpu_hz {
+            runner.set_cpu_hz(hz);
+        }
+
+        if let Some(max_dt) = self.max_dt {
+            runner.set_max_dt(max_dt);
+        }
+
+        Ok(runner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{u4, u12};
+
+    #[test]
+    fn invalid_cpu_hz_is_an_error() {
+        for hz in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let result = Chip8RunnerBuilder::new().cpu_hz(hz).build();
+            assert!(matches!(result, Err(Chip8Error::InvalidCpuHz { .. })));
+        }
+    }
+
+    #[test]
+    fn invalid_max_dt_is_an_error() {
+        for max_dt in [0.0, -0.1, f32::NAN] {
+            let result = Chip8RunnerBuilder::new().max_dt(max_dt).build();
+            assert!(matches!(result, Err(Chip8Error::InvalidMaxDt { .. })));
+        }
+    }
+
+    #[test]
+    fn valid_settings_are_applied() {
+        let runner = Chip8RunnerBuilder::new()
+            .cpu_hz(512.0)
+            .max_dt(0.05)
+            .build()
+            .unwrap();
+        assert_eq!(runner.cpu_hz(), 512.0);
+        assert_eq!(runner.max_dt(), 0.05);
+    }
+
+    #[test]
+    fn into_chip8_keeps_the_machine_state() {
+        // 6A07: VA = 7, 1202: jump to self
+        let mut runner = Chip8RunnerBuilder::new()
+            .rom(&[0x6A, 0x07, 0x12, 0x02])
+            .max_stack_depth(4)
+            .build()
+            .unwrap();
+        runner.set_cpu_hz(512.0);
+        runner.update(1.0 / 64.0).unwrap();
+
+        let chip8 = runner.into_chip8();
+        assert_eq!(chip8.reg(u4::new(0xA)), 7);
+        assert_eq!(chip8.pc(), u12::new(0x202));
+        assert_eq!(chip8.max_stack_depth(), 4);
+    }
+}
@@ -1,19 +1,44 @@
 use super::{
-    Chip8Error, Chip8Result, DISPLAY_X, DISPLAY_Y, Display, FONT, FONT_END_ADDRESS,
-    FONT_START_ADDRESS, Opcode,
+    BIG_FONT, BIG_FONT_END_ADDRESS, BIG_FONT_START_ADDRESS, Chip8Error, Chip8Result, DISPLAY_X,
+    DISPLAY_Y, Display, FONT, FONT_END_ADDRESS, FONT_START_ADDRESS, KNOWN_ROMS, Opcode, Quirks,
+    rng::Chip8Rng,
 };
 use crate::{u4, u12};
+use std::{collections::HashMap, fmt, io::Read, ops::Range};
 
 // The constants are specified by the CHIP-8 specification
-const ROM_START_ADDRESS: usize = 0x200;
-pub(crate) const MEMORY_SIZE: usize = 4096;
+pub const ROM_START_ADDRESS: usize = 0x200;
+pub const MEMORY_SIZE: usize = 4096;
+/// The default maximum number of nested subroutine calls.
+pub const DEFAULT_MAX_STACK_DEPTH: usize = 16;
+/// A display plane with one u64 per row, the leftmost pixel being the highest bit.
+pub(crate) type PackedPlane = [u64; DISPLAY_Y];
+// A row has to fit in a u64 exactly
+const _: () = assert!(DISPLAY_X == u64::BITS as usize);
+
+/// Size in bytes of the display packed at one bit per pixel.
+pub const PACKED_DISPLAY_SIZE: usize = DISPLAY_X * DISPLAY_Y / 8;
+/// The XO-CHIP audio pitch set on startup, which plays sound at its base frequency.
+pub const DEFAULT_PITCH: u8 = 64;
+
+/// Callback invoked with the machine state, raw word and decoded opcode before each instruction
+/// executes.
+pub type TraceHook = Box<dyn FnMut(&Chip8, u16, &Opcode)>;
+
+/// Callback invoked with the key and its new state (true = pressed) whenever a key changes.
+pub type KeyChangeHook = Box<dyn FnMut(u4, bool)>;
 
 /// CHIP-8 virtual machine state
 pub struct Chip8 {
     /// 4KB memory array
     pub(crate) memory: [u8; MEMORY_SIZE],
-    /// Display buffer: 64x32 monochrome pixels
-    pub(crate) display: Display<bool>,
+    /// Display buffer: 64x32 monochrome pixels, one row per u64 with the leftmost pixel
+    /// in the highest bit
+    pub(crate) display: PackedPlane,
+    /// Second display plane, only drawn to in XO-CHIP mode
+    pub(crate) display2: PackedPlane,
+    /// Bitmask of the display planes affected by drawing (bit 0 = first plane)
+    pub(crate) plane_mask: u8,
 
     /// General-purpose registers V0-VF (VF is used as a flag register)
     pub(crate) v: [u8; 16],
@@ -23,58 +48,429 @@ pub struct Chip8 {
     pub(crate) i: u12,
     /// Call stack for subroutine returns
     pub(crate) stack: Vec<u12>,
+    /// Maximum number of entries in the call stack
+    pub(crate) max_stack_depth: usize,
 
     /// Delay timer: decrements at 60Hz until it reaches 0
     pub(crate) delay_timer: u8,
     /// Sound timer: decrements at 60Hz, beeps while non-zero
     pub(crate) sound_timer: u8,
+    /// XO-CHIP audio pitch set by Fx3A
+    pub(crate) pitch: u8,
 
     /// Tracks which key is waiting to be released for the FX0A instruction
     pub(crate) wait_release_key: Option<u8>,
     /// Keypad state: 16 keys mapped as booleans (true = pressed)
     pub(crate) keypad: [bool; 16],
+
+    /// Font set copied into memory on load
+    pub(crate) font: [u8; FONT.len()],
+
+    /// Address the ROM is loaded at and execution starts from
+    pub(crate) start_address: usize,
+    /// The currently loaded ROM, kept to restore memory on reset
+    pub(crate) rom: Vec<u8>,
+
+    /// Instruction behaviors that differ between interpreters
+    pub(crate) quirks: Quirks,
+    /// Random number generator used by the Cxnn instruction
+    pub(crate) rng: Chip8Rng,
+
+    /// Optional hook called before each instruction is executed
+    pub(crate) trace: Option<TraceHook>,
+    /// Optional hook called when a key is pressed or released
+    pub(crate) on_key_change: Option<KeyChangeHook>,
+    /// Number of times each instruction was executed, when profiling is enabled
+    pub(crate) profile: Option<HashMap<&'static str, u64>>,
 }
 
 impl Chip8 {
+    /// Creates a machine with the default font in memory and no ROM loaded.
     pub fn new() -> Self {
-        Chip8 {
+        let mut chip8 = Chip8 {
             memory: [0; MEMORY_SIZE],
-            display: [[false; DISPLAY_X]; DISPLAY_Y],
+            display: [0; DISPLAY_Y],
+            display2: [0; DISPLAY_Y],
+            plane_mask: 0b01,
             v: [0; 16],
             pc: u12::new(ROM_START_ADDRESS as u16),
             i: u12::new(0),
             stack: Vec::new(),
+            max_stack_depth: DEFAULT_MAX_STACK_DEPTH,
             delay_timer: 0,
             sound_timer: 0,
+            pitch: DEFAULT_PITCH,
             wait_release_key: None,
             keypad: [false; 16],
+            font: FONT,
+            start_address: ROM_START_ADDRESS,
+            rom: Vec::new(),
+            quirks: Quirks::default(),
+            rng: Chip8Rng::from_entropy(),
+            trace: None,
+            on_key_change: None,
+            profile: None,
+        };
+        chip8.load_fonts();
+        chip8
+    }
+
+    /// Creates a machine that loads ROMs at `address` instead of the default 0x200.
+    ///
+    /// The address must be past the font region and leave room for at least one instruction.
+    pub fn with_start_address(address: u16) -> Result<Self, Chip8Error> {
+        let address = usize::from(address);
+        if !(BIG_FONT_END_ADDRESS..=MEMORY_SIZE - 2).contains(&address) {
+            return Err(Chip8Error::InvalidStartAddress {
+                address,
+                min: BIG_FONT_END_ADDRESS,
+                max: MEMORY_SIZE - 2,
+            });
         }
+
+        let mut chip8 = Self::new();
+        chip8.start_address = address;
+        chip8.pc = u12::new(address as u16);
+        Ok(chip8)
+    }
+
+    /// Copies the font sets into memory.
+    fn load_fonts(&mut self) {
+        self.memory[FONT_START_ADDRESS..FONT_END_ADDRESS].copy_from_slice(&self.font);
+        self.memory[BIG_FONT_START_ADDRESS..BIG_FONT_END_ADDRESS].copy_from_slice(&BIG_FONT);
     }
 
-    /// Loads a ROM into memory and initializes the font set.
+    /// Loads a ROM into memory and refreshes the font set.
     pub fn load(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
-        // Load font into memory
-        self.memory[FONT_START_ADDRESS..FONT_END_ADDRESS].copy_from_slice(&FONT);
+        self.load_fonts();
 
         // Load ROM into memory
-        let rom_end = ROM_START_ADDRESS + rom.len();
+        let rom_end = self.start_address + rom.len();
         self.memory
-            .get_mut(ROM_START_ADDRESS..rom_end)
+            .get_mut(self.start_address..rom_end)
             .ok_or(Chip8Error::RomLoadError {
                 size: rom.len(),
-                max_size: MEMORY_SIZE - ROM_START_ADDRESS,
+                max_size: MEMORY_SIZE - self.start_address,
             })?
             .copy_from_slice(rom);
+        self.rom = rom.to_vec();
 
         // Set program counter to start of ROM
-        self.pc = u12::new(ROM_START_ADDRESS as u16);
+        self.pc = u12::new(self.start_address as u16);
+
+        Ok(())
+    }
+
+    /// Restarts the loaded ROM from the beginning.
+    ///
+    /// Memory is restored to its state right after loading, and the registers, stack, timers,
+    /// display and keypad are cleared. Quirks, the font and the hooks are kept.
+    pub fn reset(&mut self) {
+        self.memory = [0; MEMORY_SIZE];
+        self.display = [0; DISPLAY_Y];
+        self.display2 = [0; DISPLAY_Y];
+        self.plane_mask = 0b01;
+        self.v = [0; 16];
+        self.i = u12::new(0);
+        self.stack.clear();
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.pitch = DEFAULT_PITCH;
+        self.wait_release_key = None;
+        self.set_keys([false; 16]);
+
+        self.load_fonts();
+        // The ROM already fit in memory when it was first loaded
+        let rom_end = self.start_address + self.rom.len();
+        self.memory[self.start_address..rom_end].copy_from_slice(&self.rom);
+        self.pc = u12::new(self.start_address as u16);
+    }
+
+    /// Replaces the default font set with a custom one and copies it into memory.
+    ///
+    /// Each glyph is 5 bytes, in order from 0 to F. Fonts with fewer than 16 glyphs are allowed,
+    /// the remaining glyphs are left blank.
+    pub fn set_font(&mut self, font: &[u8]) -> Result<(), Chip8Error> {
+        let mut new_font = [0; FONT.len()];
+        new_font
+            .get_mut(..font.len())
+            .ok_or(Chip8Error::FontLoadError {
+                size: font.len(),
+                max_size: FONT.len(),
+            })?
+            .copy_from_slice(font);
+
+        self.font = new_font;
+        self.memory[FONT_START_ADDRESS..FONT_END_ADDRESS].copy_from_slice(&self.font);
+        Ok(())
+    }
+
+    /// Reads a ROM from a reader until EOF and loads it into memory.
+    pub fn load_from_reader<R: Read>(&mut self, mut reader: R) -> Result<(), Chip8Error> {
+        let mut rom = Vec::new();
+        reader.read_to_end(&mut rom)?;
+        self.load(&rom)
+    }
+
+    /// Decodes a ROM from a hex string and loads it into memory. Whitespace is ignored.
+    pub fn load_hex(&mut self, s: &str) -> Result<(), Chip8Error> {
+        let digits: Vec<u8> = s
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace())
+            .map(|c| {
+                c.to_digit(16)
+                    .map(|digit| digit as u8)
+                    .ok_or_else(|| Chip8Error::RomDecodeError {
+                        reason: format!("invalid hex digit '{c}'"),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        if !digits.len().is_multiple_of(2) {
+            return Err(Chip8Error::RomDecodeError {
+                reason: "odd number of hex digits".to_string(),
+            });
+        }
+
+        let rom: Vec<u8> = digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect();
+        self.load(&rom)
+    }
+
+    /// Decodes a ROM from a standard base64 string and loads it into memory.
+    #[cfg(feature = "base64")]
+    pub fn load_base64(&mut self, s: &str) -> Result<(), Chip8Error> {
+        use base64::Engine;
+
+        let rom = base64::engine::general_purpose::STANDARD
+            .decode(s.trim())
+            .map_err(|e| Chip8Error::RomDecodeError {
+                reason: e.to_string(),
+            })?;
+        self.load(&rom)
+    }
+
+    /// Returns the address the ROM is loaded at.
+    pub fn start_address(&self) -> u16 {
+        self.start_address as u16
+    }
+
+    /// Returns the size of the loaded ROM in bytes.
+    pub fn rom_len(&self) -> usize {
+        self.rom.len()
+    }
+
+    /// Returns the number of bytes left free after the end of the loaded ROM.
+    pub fn free_memory(&self) -> usize {
+        MEMORY_SIZE - self.start_address - self.rom.len()
+    }
+
+    /// Returns the quirks used when executing instructions.
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Set the quirks used when executing instructions.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Returns the maximum number of nested subroutine calls.
+    pub fn max_stack_depth(&self) -> usize {
+        self.max_stack_depth
+    }
+
+    /// Set the maximum number of nested subroutine calls.
+    ///
+    /// Calling a subroutine with a full stack fails with `StackOverflow`. The original
+    /// COSMAC VIP interpreter allowed 12 levels, most later interpreters allow 16.
+    pub fn set_max_stack_depth(&mut self, depth: usize) {
+        self.max_stack_depth = depth;
+    }
+
+    /// Seed the random number generator, making the Cxnn instruction deterministic.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Chip8Rng::from_seed(seed);
+    }
+
+    /// Get the value of register Vx.
+    pub fn reg(&self, x: u4) -> u8 {
+        self.v[x]
+    }
+
+    /// Set the value of register Vx.
+    pub fn set_reg(&mut self, x: u4, value: u8) {
+        self.v[x] = value;
+    }
+
+    /// Get the index register I.
+    pub fn index(&self) -> u12 {
+        self.i
+    }
+
+    /// Set the index register I.
+    pub fn set_index(&mut self, value: u12) {
+        self.i = value;
+    }
+
+    /// Get the program counter, the address of the next instruction to execute.
+    pub fn pc(&self) -> u12 {
+        self.pc
+    }
+
+    /// Set the program counter, the next instruction is fetched from this address.
+    pub fn set_pc(&mut self, value: u12) {
+        self.pc = value;
+    }
+
+    /// Get the call stack, with the most recent return address last.
+    pub fn stack(&self) -> &[u12] {
+        &self.stack
+    }
 
+    /// Push a return address onto the call stack, failing if it is already at the depth limit.
+    pub fn stack_push(&mut self, address: u12) -> Result<(), Chip8Error> {
+        if self.stack.len() >= self.max_stack_depth {
+            return Err(Chip8Error::StackOverflow);
+        }
+        self.stack.push(address);
+        Ok(())
+    }
+
+    /// Pop the most recent return address off the call stack, if any.
+    pub fn stack_pop(&mut self) -> Option<u12> {
+        self.stack.pop()
+    }
+
+    /// Get the whole memory. Use `mem_set` to write to it.
+    pub fn memory(&self) -> &[u8; MEMORY_SIZE] {
+        &self.memory
+    }
+
+    /// Get a range of memory, or None if the range extends past the end of memory.
+    pub fn memory_range(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.memory.get(range)
+    }
+
+    /// Reads a byte from memory, failing if the address is past the end of memory.
+    pub fn mem_get(&self, address: u16) -> Result<u8, Chip8Error> {
+        self.memory
+            .get(usize::from(address))
+            .copied()
+            .ok_or(Chip8Error::MemoryOutOfBounds { address })
+    }
+
+    /// Writes a byte to memory, failing if the address is past the end of memory.
+    pub fn mem_set(&mut self, address: u16, value: u8) -> Result<(), Chip8Error> {
+        *self
+            .memory
+            .get_mut(usize::from(address))
+            .ok_or(Chip8Error::MemoryOutOfBounds { address })? = value;
         Ok(())
     }
 
     /// Executes a single CPU cycle (fetch, decode, execute).
     pub fn cpu_cycle(&mut self) -> Result<Chip8Result, Chip8Error> {
-        self.execute(Opcode::decode(self.fetch()))
+        self.step().map(|(_, result)| result)
+    }
+
+    /// Executes a single CPU cycle like `cpu_cycle`, also returning the decoded opcode that ran.
+    pub fn step(&mut self) -> Result<(Opcode, Chip8Result), Chip8Error> {
+        let raw = self.fetch()?;
+        let opcode = Opcode::decode(raw);
+
+        // The hook is taken out while it runs so it can observe the machine
+        if let Some(mut trace) = self.trace.take() {
+            trace(self, raw, &opcode);
+            self.trace = Some(trace);
+        }
+
+        if let Some(profile) = &mut self.profile {
+            *profile.entry(opcode.name()).or_default() += 1;
+        }
+
+        let result = self.execute(opcode.clone())?;
+        Ok((opcode, result))
+    }
+
+    /// Returns a human readable, multi-line dump of the registers, the call stack and the
+    /// 16 bytes of memory starting at I (where Fx33 stores BCD digits).
+    pub fn dump_state(&self) -> String {
+        let stack: Vec<String> = self
+            .stack
+            .iter()
+            .map(|addr| format!("{addr:03X}"))
+            .collect();
+        let memory: Vec<String> = (0..16)
+            .map_while(|offset| self.mem_get(usize::from(self.i) as u16 + offset).ok())
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+
+        format!(
+            "{self}\nStack: [{}]\nMemory at I ({:03X}): {}\n",
+            stack.join(", "),
+            self.i,
+            memory.join(" ")
+        )
+    }
+
+    /// Returns true if the next instruction is a jump to itself.
+    ///
+    /// Many ROMs end with such an idle loop, which never changes the machine state again
+    /// except through the timers.
+    pub fn is_halted_loop(&self) -> bool {
+        self.next_opcode() == Some(Opcode::Jump { nnn: self.pc })
+    }
+
+    /// Returns true if the machine is parked on a Fx0A instruction, waiting for a key to be
+    /// pressed and released.
+    pub fn is_waiting_for_key(&self) -> bool {
+        matches!(self.next_opcode(), Some(Opcode::WaitForKey { .. }))
+    }
+
+    /// Decodes the instruction at the program counter without executing it.
+    /// Returns None if the instruction can't be fetched.
+    pub fn next_opcode(&self) -> Option<Opcode> {
+        self.fetch().ok().map(Opcode::decode)
+    }
+
+    /// Start counting how many times each instruction is executed.
+    ///
+    /// Any previous counts are discarded.
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(HashMap::new());
+    }
+
+    /// Returns the number of times each instruction was executed, keyed by `Opcode::name`,
+    /// or `None` if profiling is not enabled.
+    pub fn opcode_profile(&self) -> Option<&HashMap<&'static str, u64>> {
+        self.profile.as_ref()
+    }
+
+    /// Decodes and executes a single instruction word against the current state, without
+    /// fetching it from memory. Useful for fuzzing the interpreter.
+    ///
+    /// PC is still incremented and jumps behave as usual. Instructions that access memory,
+    /// such as Dxyn or Fx55, still read and write the real memory at I.
+    pub fn execute_raw(&mut self, word: u16) -> Result<Chip8Result, Chip8Error> {
+        self.execute(Opcode::decode(word))
+    }
+
+    /// Installs a hook that observes every instruction before it executes.
+    ///
+    /// Pass `None` to remove a previously installed hook.
+    pub fn set_trace(&mut self, trace: Option<TraceHook>) {
+        self.trace = trace;
+    }
+
+    /// Installs a hook that is called when a key is pressed or released through `set_key`,
+    /// `set_keys` or `reset`. Setting a key to the state it is already in doesn't call it.
+    ///
+    /// Pass `None` to remove a previously installed hook.
+    pub fn set_on_key_change(&mut self, on_key_change: Option<KeyChangeHook>) {
+        self.on_key_change = on_key_change;
     }
 
     /// Updates the delay and sound timers. Should be called at 60Hz.
@@ -83,32 +479,842 @@ impl Chip8 {
         self.sound_timer = self.sound_timer.saturating_sub(1);
     }
 
+    /// Returns the current delay timer value.
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    /// Returns the current sound timer value.
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
     /// Returns true if the sound timer is greater than zero, indicating a beep should be played.
     pub fn should_beep(&self) -> bool {
         self.sound_timer > 0
     }
 
+    /// Returns the XO-CHIP audio pitch set by Fx3A.
+    pub fn sound_pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// Returns how much the pitch raises the sound frequency, 1.0 at the default pitch.
+    ///
+    /// XO-CHIP raises the frequency by an octave every 48 steps of pitch.
+    pub fn sound_pitch_ratio(&self) -> f32 {
+        2f32.powf((f32::from(self.pitch) - f32::from(DEFAULT_PITCH)) / 48.0)
+    }
+
     /// Set the state of a key on the keypad.
     pub fn set_key(&mut self, key: u4, pressed: bool) {
+        if self.keypad[key] == pressed {
+            return;
+        }
+
         self.keypad[key] = pressed;
+        if let Some(on_key_change) = &mut self.on_key_change {
+            on_key_change(key, pressed);
+        }
+    }
+
+    /// Set the state of all keys on the keypad at once.
+    ///
+    /// Releasing a key that Fx0A is waiting on completes the wait on the next cycle,
+    /// the same as releasing it with `set_key`.
+    pub fn set_keys(&mut self, state: [bool; 16]) {
+        for (key, pressed) in state.into_iter().enumerate() {
+            self.set_key(u4::new(key as u8), pressed);
+        }
+    }
+
+    /// Get the state of the whole keypad (true = pressed).
+    pub fn keypad(&self) -> [bool; 16] {
+        self.keypad
+    }
+
+    /// Returns the keys that are currently pressed, in ascending order.
+    pub fn pressed_keys(&self) -> impl Iterator<Item = u4> + use<> {
+        let keypad = self.keypad;
+        (0..16u8)
+            .filter(move |&key| keypad[key as usize])
+            .map(u4::new)
     }
 
-    /// Get the state of a pixel on the display (true = on, false = off).
+    /// Get the state of a pixel on the display (true = on in any plane, false = off).
     pub fn get_display_pixel(&self, y: usize, x: usize) -> bool {
-        self.display[y][x]
+        self.get_display_planes(y, x) != 0
+    }
+
+    /// Get the planes a pixel is lit in as a bitmask (bit 0 = first plane, bit 1 = second plane).
+    pub fn get_display_planes(&self, y: usize, x: usize) -> u8 {
+        let bit = DISPLAY_X - 1 - x;
+        ((self.display[y] >> bit) & 1 | ((self.display2[y] >> bit) & 1) << 1) as u8
+    }
+
+    /// Get the display with one u64 per row and the leftmost pixel in the highest bit.
+    /// A pixel is set if it is lit in any plane.
+    pub fn display_rows_packed(&self) -> [u64; DISPLAY_Y] {
+        std::array::from_fn(|y| self.display[y] | self.display2[y])
+    }
+
+    /// Get the display with one bool per pixel (true = on in any plane, false = off).
+    pub fn display(&self) -> Display<bool> {
+        std::array::from_fn(|y| std::array::from_fn(|x| self.get_display_pixel(y, x)))
+    }
+
+    /// Packs the display into bytes of 8 horizontal pixels each, most significant bit first,
+    /// row by row. A pixel is set if it is lit in any plane.
+    pub fn framebuffer_packed(&self) -> Vec<u8> {
+        self.display_rows_packed()
+            .iter()
+            .flat_map(|row| row.to_be_bytes())
+            .collect()
+    }
+
+    /// Restores the display from the format produced by [`Chip8::framebuffer_packed`].
+    /// The pixels are written to the first plane and the second plane is cleared.
+    pub fn framebuffer_from_packed(&mut self, packed: &[u8; PACKED_DISPLAY_SIZE]) {
+        for (row, bytes) in self.display.iter_mut().zip(packed.chunks_exact(8)) {
+            *row = u64::from_be_bytes(bytes.try_into().unwrap());
+        }
+        self.display2 = [0; DISPLAY_Y];
+    }
+
+    /// Computes a stable FNV-1a hash of the display contents, useful for golden tests.
+    pub fn display_hash(&self) -> u64 {
+        fnv1a(
+            (0..DISPLAY_Y).flat_map(|y| (0..DISPLAY_X).map(move |x| self.get_display_planes(y, x))),
+        )
+    }
+
+    /// Computes a stable FNV-1a hash of the loaded ROM, used to identify known ROMs.
+    pub fn rom_hash(&self) -> u64 {
+        fnv1a(self.rom.iter().copied())
+    }
+
+    /// Looks up the loaded ROM in a built-in table of known ROMs and returns the quirks it
+    /// needs, or None if the ROM isn't known.
+    pub fn detect_quirks_from_hash(&self) -> Option<Quirks> {
+        let hash = self.rom_hash();
+        KNOWN_ROMS
+            .iter()
+            .find(|rom| rom.hash == hash)
+            .map(|rom| rom.quirks)
+    }
+
+    /// Renders the display as text, one line per row, with `#` for lit pixels and spaces otherwise.
+    pub fn display_to_string(&self) -> String {
+        self.render_ascii('#', ' ')
+    }
+
+    /// Renders the display as text, one line per row, using `on` and `off` for each pixel.
+    pub fn render_ascii(&self, on: char, off: char) -> String {
+        let mut out = String::with_capacity((DISPLAY_X + 1) * DISPLAY_Y);
+        for y in 0..DISPLAY_Y {
+            for x in 0..DISPLAY_X {
+                out.push(if self.get_display_pixel(y, x) {
+                    on
+                } else {
+                    off
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the display as text using Unicode half blocks, packing two rows into each line.
+    pub fn render_half_blocks(&self) -> String {
+        let mut out = String::with_capacity((DISPLAY_X + 1) * DISPLAY_Y.div_ceil(2));
+        for y in (0..DISPLAY_Y).step_by(2) {
+            for x in 0..DISPLAY_X {
+                let top = self.get_display_pixel(y, x);
+                let bottom = y + 1 < DISPLAY_Y && self.get_display_pixel(y + 1, x);
+
+                out.push(match (top, bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Returns the display plane with the given index (0 or 1).
+    pub(crate) fn plane_mut(&mut self, plane: usize) -> &mut PackedPlane {
+        match plane {
+            0 => &mut self.display,
+            _ => &mut self.display2,
+        }
+    }
+
+    /// Returns the indices of the planes selected for drawing.
+    pub(crate) fn selected_planes(&self) -> impl Iterator<Item = usize> + use<> {
+        let mask = self.plane_mask;
+        (0..2).filter(move |plane| mask & (1 << plane) != 0)
     }
 
     /// Fetches the next 16-bit opcode from memory.
-    fn fetch(&self) -> u16 {
-        let high = self.memory[self.pc];
-        let low = self.memory[self.pc.wrapping_add(1)];
+    fn fetch(&self) -> Result<u16, Chip8Error> {
+        let pc = usize::from(self.pc) as u16;
+        if self.quirks.align_pc && pc % 2 == 1 {
+            return Err(Chip8Error::MisalignedPc { address: pc });
+        }
 
-        u16::from_be_bytes([high, low])
+        let high = self.mem_get(pc)?;
+        let low = self.mem_get(pc + 1)?;
+
+        Ok(u16::from_be_bytes([high, low]))
     }
 }
 
+/// Computes the 64-bit FNV-1a hash of a byte sequence.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+    bytes.into_iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
 impl Default for Chip8 {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Summarizes the registers, leaving out memory and the display.
+impl fmt::Debug for Chip8 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chip8")
+            .field("pc", &format_args!("{:03X}", self.pc))
+            .field("i", &format_args!("{:03X}", self.i))
+            .field("v", &format_args!("{:02X?}", self.v))
+            .field("delay_timer", &self.delay_timer)
+            .field("sound_timer", &self.sound_timer)
+            .field("stack_depth", &self.stack.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// One line summary of the registers, e.g. `PC=200 I=000 V0=00 ... VF=00 DT=00 ST=00 SP=0`.
+impl fmt::Display for Chip8 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PC={:03X} I={:03X}", self.pc, self.i)?;
+        for (idx, value) in self.v.iter().enumerate() {
+            write!(f, " V{idx:X}={value:02X}")?;
+        }
+        write!(
+            f,
+            " DT={:02X} ST={:02X} SP={}",
+            self.delay_timer,
+            self.sound_timer,
+            self.stack.len()
+        )
+    }
+}
+
+/// Clones the machine state. The hooks can't be cloned, so the clone has none.
+impl Clone for Chip8 {
+    fn clone(&self) -> Self {
+        Self {
+            memory: self.memory,
+            display: self.display,
+            display2: self.display2,
+            plane_mask: self.plane_mask,
+            v: self.v,
+            pc: self.pc,
+            i: self.i,
+            stack: self.stack.clone(),
+            max_stack_depth: self.max_stack_depth,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            pitch: self.pitch,
+            wait_release_key: self.wait_release_key,
+            keypad: self.keypad,
+            font: self.font,
+            start_address: self.start_address,
+            rom: self.rom.clone(),
+            quirks: self.quirks,
+            rng: self.rng.clone(),
+            trace: None,
+            on_key_change: None,
+            profile: self.profile.clone(),
+        }
+    }
+}
+
+/// Compares the machine state, ignoring the hooks.
+impl PartialEq for Chip8 {
+    fn eq(&self, other: &Self) -> bool {
+        // Destructured so that adding a field fails to compile until it is compared here
+        let Self {
+            memory,
+            display,
+            display2,
+            plane_mask,
+            v,
+            pc,
+            i,
+            stack,
+            max_stack_depth,
+            delay_timer,
+            sound_timer,
+            pitch,
+            wait_release_key,
+            keypad,
+            font,
+            start_address,
+            rom,
+            quirks,
+            rng,
+            trace: _,
+            on_key_change: _,
+            profile,
+        } = self;
+
+        *memory == other.memory
+            && *display == other.display
+            && *display2 == other.display2
+            && *plane_mask == other.plane_mask
+            && *v == other.v
+            && *pc == other.pc
+            && *i == other.i
+            && *stack == other.stack
+            && *max_stack_depth == other.max_stack_depth
+            && *delay_timer == other.delay_timer
+            && *sound_timer == other.sound_timer
+            && *pitch == other.pitch
+            && *wait_release_key == other.wait_release_key
+            && *keypad == other.keypad
+            && *font == other.font
+            && *start_address == other.start_address
+            && *rom == other.rom
+            && *quirks == other.quirks
+            && *rng == other.rng
+            && *profile == other.profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emu::{FONT_CHAR_SIZE, font_char_address};
+
+    /// The IBM logo ROM, which draws the logo and then jumps to itself.
+    const IBM_LOGO: &[u8] = &[
+        0x00, 0xE0, 0xA2, 0x2A, 0x60, 0x0C, 0x61, 0x08, 0xD0, 0x1F, 0x70, 0x09, 0xA2, 0x39, 0xD0,
+        0x1F, 0xA2, 0x48, 0x70, 0x08, 0xD0, 0x1F, 0x70, 0x04, 0xA2, 0x57, 0xD0, 0x1F, 0x70, 0x08,
+        0xA2, 0x66, 0xD0, 0x1F, 0x70, 0x08, 0xA2, 0x75, 0xD0, 0x1F, 0x12, 0x28, 0xFF, 0x00, 0xFF,
+        0x00, 0x3C, 0x00, 0x3C, 0x00, 0x3C, 0x00, 0x3C, 0x00, 0xFF, 0x00, 0xFF, 0xFF, 0x00, 0xFF,
+        0x00, 0x38, 0x00, 0x3F, 0x00, 0x3F, 0x00, 0x38, 0x00, 0xFF, 0x00, 0xFF, 0x80, 0x00, 0xE0,
+        0x00, 0xE0, 0x00, 0x80, 0x00, 0x80, 0x00, 0xE0, 0x00, 0xE0, 0x00, 0x80, 0xF8, 0x00, 0xFC,
+        0x00, 0x3E, 0x00, 0x3F, 0x00, 0x3B, 0x00, 0x39, 0x00, 0xF8, 0x00, 0xF8, 0x03, 0x00, 0x07,
+        0x00, 0x0F, 0x00, 0xBF, 0x00, 0xFB, 0x00, 0xF3, 0x00, 0xE3, 0x00, 0x43, 0xE0, 0x00, 0xE0,
+        0x00, 0x80, 0x00, 0x80, 0x00, 0x80, 0x00, 0x80, 0x00, 0xE0, 0x00, 0xE0,
+    ];
+
+    /// Draws a sprite across the right edge and shows the collision flag as a digit.
+    const WRAP_TEST: &[u8] = include_bytes!("../../roms/wrap_test.ch8");
+
+    fn load(rom: &[u8]) -> Chip8 {
+        let mut chip8 = Chip8::new();
+        chip8.load(rom).unwrap();
+        chip8
+    }
+
+    #[test]
+    fn known_rom_quirks_are_detected() {
+        let chip8 = load(IBM_LOGO);
+        assert_eq!(chip8.rom_hash(), 0x64E4_5391_BA02_38A1);
+        assert_eq!(chip8.detect_quirks_from_hash(), Some(Quirks::COSMAC_VIP));
+    }
+
+    #[test]
+    fn detected_quirks_are_needed_by_the_rom() {
+        let mut chip8 = load(WRAP_TEST);
+        let quirks = chip8.detect_quirks_from_hash().unwrap();
+        assert_ne!(quirks, Quirks::default());
+        assert!(quirks.draw_wrap);
+
+        // The top rows of the 0 and 1 glyphs are F0 and 20, drawn at (30, 13)
+        let run_to_halt = |chip8: &mut Chip8| {
+            while !chip8.is_halted_loop() {
+                chip8.cpu_cycle().unwrap();
+            }
+        };
+        run_to_halt(&mut chip8);
+        assert!(chip8.get_display_pixel(13, 30));
+
+        chip8.set_quirks(quirks);
+        chip8.reset();
+        run_to_halt(&mut chip8);
+        assert!(!chip8.get_display_pixel(13, 30));
+        assert!(chip8.get_display_pixel(13, 32));
+    }
+
+    #[test]
+    fn unknown_rom_keeps_default_quirks() {
+        let mut chip8 = load(&[0x12, 0x00]);
+        assert_eq!(chip8.detect_quirks_from_hash(), None);
+
+        if let Some(quirks) = chip8.detect_quirks_from_hash() {
+            chip8.set_quirks(quirks);
+        }
+        assert_eq!(chip8.quirks(), Quirks::default());
+    }
+
+    #[test]
+    fn framebuffer_round_trips() {
+        let mut chip8 = load(IBM_LOGO);
+        for _ in 0..20 {
+            chip8.cpu_cycle().unwrap();
+        }
+
+        let packed: [u8; PACKED_DISPLAY_SIZE] = chip8.framebuffer_packed().try_into().unwrap();
+        let mut restored = Chip8::new();
+        restored.framebuffer_from_packed(&packed);
+
+        assert_eq!(restored.display(), chip8.display());
+        assert_eq!(restored.display_rows_packed(), chip8.display_rows_packed());
+        assert_eq!(restored.display_hash(), chip8.display_hash());
+    }
+
+    #[test]
+    fn framebuffer_is_packed_msb_first() {
+        let mut packed = [0; PACKED_DISPLAY_SIZE];
+        packed[0] = 0x80;
+        packed[DISPLAY_X / 8 + 7] = 0x01;
+
+        let mut chip8 = Chip8::new();
+        chip8.display2[5] = u64::MAX;
+        chip8.framebuffer_from_packed(&packed);
+
+        assert!(chip8.get_display_pixel(0, 0));
+        assert!(!chip8.get_display_pixel(0, 1));
+        assert!(chip8.get_display_pixel(1, DISPLAY_X - 1));
+        // The second plane is cleared
+        assert!(!chip8.get_display_pixel(5, 0));
+        assert_eq!(chip8.display_rows_packed()[1], 1);
+        assert_eq!(chip8.framebuffer_packed(), packed);
+    }
+
+    #[test]
+    fn trace_hook_sees_executed_opcodes() {
+        let mut chip8 = load(IBM_LOGO);
+        let traced = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let sink = traced.clone();
+        chip8.set_trace(Some(Box::new(move |_, raw, opcode: &Opcode| {
+            sink.borrow_mut().push((raw, opcode.clone()));
+        })));
+
+        for _ in 0..5 {
+            chip8.cpu_cycle().unwrap();
+        }
+
+        let (x, y) = (u4::new(0), u4::new(1));
+        assert_eq!(
+            *traced.borrow(),
+            [
+                (0x00E0, Opcode::ClearDisplay),
+                (
+                    0xA22A,
+                    Opcode::SetIndexImm {
+                        nnn: u12::new(0x22A)
+                    }
+                ),
+                (0x600C, Opcode::SetRegImm { x, nn: 0x0C }),
+                (0x6108, Opcode::SetRegImm { x: y, nn: 0x08 }),
+                (
+                    0xD01F,
+                    Opcode::Draw {
+                        x,
+                        y,
+                        n: u4::new(0xF)
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn ibm_logo_display_hash() {
+        let mut chip8 = load(IBM_LOGO);
+        while !chip8.is_halted_loop() {
+            chip8.cpu_cycle().unwrap();
+        }
+
+        assert_eq!(
+            chip8.display_hash(),
+            0x1F1D_341C_AB07_E169,
+            "unexpected display:\n{}",
+            chip8.display_to_string()
+        );
+    }
+
+    #[test]
+    fn nested_calls_overflow_with_an_error() {
+        // 2200: call itself
+        let mut chip8 = load(&[0x22, 0x00]);
+        for _ in 0..DEFAULT_MAX_STACK_DEPTH {
+            chip8.cpu_cycle().unwrap();
+        }
+
+        assert!(matches!(chip8.cpu_cycle(), Err(Chip8Error::StackOverflow)));
+        assert_eq!(chip8.stack().len(), DEFAULT_MAX_STACK_DEPTH);
+    }
+
+    #[test]
+    fn stack_push_past_the_limit_is_an_error() {
+        let mut chip8 = Chip8::new();
+        chip8.set_max_stack_depth(2);
+        chip8.stack_push(u12::new(0x200)).unwrap();
+        chip8.stack_push(u12::new(0x300)).unwrap();
+
+        assert!(matches!(
+            chip8.stack_push(u12::new(0x400)),
+            Err(Chip8Error::StackOverflow)
+        ));
+        assert_eq!(chip8.stack(), [u12::new(0x200), u12::new(0x300)]);
+    }
+
+    #[test]
+    fn fetch_past_the_end_of_memory_is_an_error() {
+        let mut chip8 = Chip8::new();
+        chip8.set_pc(u12::new(0xFFF));
+
+        assert!(matches!(
+            chip8.cpu_cycle(),
+            Err(Chip8Error::MemoryOutOfBounds { address: 0x1000 })
+        ));
+    }
+
+    #[test]
+    fn memory_access_stops_at_the_last_address() {
+        let mut chip8 = Chip8::new();
+        chip8.mem_set(0xFFF, 0xAB).unwrap();
+
+        assert_eq!(chip8.mem_get(0xFFF).unwrap(), 0xAB);
+        assert!(matches!(
+            chip8.mem_get(0x1000),
+            Err(Chip8Error::MemoryOutOfBounds { address: 0x1000 })
+        ));
+        assert!(matches!(
+            chip8.mem_set(0x1000, 0),
+            Err(Chip8Error::MemoryOutOfBounds { address: 0x1000 })
+        ));
+
+        assert_eq!(chip8.memory_range(0xFFF..0x1000), Some(&[0xAB][..]));
+        assert_eq!(chip8.memory_range(0xFFF..0x1001), None);
+        assert_eq!(chip8.memory_range(0x1000..0x1000), Some(&[][..]));
+    }
+
+    #[test]
+    fn new_machine_points_fx29_at_the_glyph() {
+        let mut chip8 = Chip8::new();
+        chip8.set_reg(u4::new(3), 5);
+
+        // F329: I = font sprite for the digit in V3
+        chip8.execute_raw(0xF329).unwrap();
+        let start = usize::from(chip8.index());
+        assert_eq!(chip8.index(), font_char_address(u4::new(5)));
+        assert_eq!(chip8.memory()[start..start + FONT_CHAR_SIZE], FONT[25..30]);
+    }
+
+    #[test]
+    fn set_keys_completes_a_pending_key_wait() {
+        // F30A: wait for a key into V3, 1202: jump to self
+        let mut chip8 = load(&[0xF3, 0x0A, 0x12, 0x02]);
+        let keys = |pressed: &[usize]| std::array::from_fn(|key| pressed.contains(&key));
+
+        chip8.set_keys(keys(&[7]));
+        chip8.cpu_cycle().unwrap();
+        assert_eq!(chip8.pc(), u12::new(0x200));
+
+        // Swapping 7 for 9 in one call releases the key being waited on
+        chip8.set_keys(keys(&[9]));
+        chip8.cpu_cycle().unwrap();
+        assert_eq!(chip8.pc(), u12::new(0x202));
+        assert_eq!(chip8.reg(u4::new(3)), 7);
+    }
+
+    #[test]
+    fn key_wait_is_reported_until_a_key_is_released() {
+        // 6001: V0 = 1, F00A: wait for a key into V0, 1204: jump to self
+        let mut chip8 = load(&[0x60, 0x01, 0xF0, 0x0A, 0x12, 0x04]);
+        assert!(!chip8.is_waiting_for_key());
+
+        chip8.cpu_cycle().unwrap();
+        chip8.cpu_cycle().unwrap();
+        assert!(chip8.is_waiting_for_key());
+
+        chip8.set_key(u4::new(0xB), true);
+        chip8.cpu_cycle().unwrap();
+        assert!(chip8.is_waiting_for_key());
+
+        chip8.set_key(u4::new(0xB), false);
+        chip8.cpu_cycle().unwrap();
+        assert!(!chip8.is_waiting_for_key());
+        assert_eq!(chip8.reg(u4::new(0)), 0xB);
+    }
+
+    #[test]
+    fn hex_roms_are_decoded() {
+        let mut chip8 = Chip8::new();
+        chip8.load_hex("00e0 A22A\n6 00C").unwrap();
+        assert_eq!(chip8.rom_len(), 6);
+        assert_eq!(
+            chip8.memory_range(0x200..0x206),
+            Some(&[0x00, 0xE0, 0xA2, 0x2A, 0x60, 0x0C][..])
+        );
+
+        assert!(matches!(
+            chip8.load_hex("00E0 A2G0"),
+            Err(Chip8Error::RomDecodeError { .. })
+        ));
+        assert!(matches!(
+            chip8.load_hex("00E"),
+            Err(Chip8Error::RomDecodeError { .. })
+        ));
+    }
+
+    #[test]
+    fn odd_jumps_fail_only_with_align_pc() {
+        // 1203: jump to an odd address, which holds 6155: V1 = 0x55
+        let rom = [0x12, 0x03, 0x00, 0x61, 0x55];
+
+        let mut chip8 = load(&rom);
+        chip8.cpu_cycle().unwrap();
+        chip8.cpu_cycle().unwrap();
+        assert_eq!(chip8.reg(u4::new(1)), 0x55);
+        assert_eq!(chip8.pc(), u12::new(0x205));
+
+        let mut chip8 = load(&rom);
+        chip8.set_quirks(Quirks {
+            align_pc: true,
+            ..Quirks::default()
+        });
+        chip8.cpu_cycle().unwrap();
+        assert!(matches!(
+            chip8.cpu_cycle(),
+            Err(Chip8Error::MisalignedPc { address: 0x203 })
+        ));
+        assert_eq!(chip8.reg(u4::new(1)), 0);
+    }
+
+    #[test]
+    fn roms_load_from_a_reader() {
+        let mut chip8 = Chip8::new();
+        chip8
+            .load_from_reader(std::io::Cursor::new(IBM_LOGO))
+            .unwrap();
+
+        assert_eq!(chip8.rom_len(), IBM_LOGO.len());
+        assert_eq!(
+            chip8.memory_range(0x200..0x200 + IBM_LOGO.len()),
+            Some(IBM_LOGO)
+        );
+        assert_eq!(chip8.pc(), u12::new(0x200));
+    }
+
+    #[test]
+    fn rom_size_and_free_memory_add_up() {
+        let chip8 = load(&[0xAA; 100]);
+
+        assert_eq!(chip8.rom_len(), 100);
+        assert_eq!(chip8.free_memory(), MEMORY_SIZE - 0x200 - 100);
+        assert_eq!(Chip8::new().free_memory(), MEMORY_SIZE - 0x200);
+    }
+
+    #[test]
+    fn custom_font_is_used_by_fx29() {
+        let mut chip8 = Chip8::new();
+        // Glyph 0 is a full block, glyph 1 a vertical line, the rest are blank
+        let font = [0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0x80, 0x80, 0x80, 0x80, 0x80];
+        chip8.set_font(&font).unwrap();
+
+        // F029: I = font sprite for V0 = 1, D015: draw it at (0, 0)
+        chip8.set_reg(u4::new(0), 1);
+        chip8.execute_raw(0xF029).unwrap();
+        let start = usize::from(chip8.index());
+        assert_eq!(chip8.memory()[start..start + FONT_CHAR_SIZE], font[5..]);
+
+        chip8.set_reg(u4::new(0), 0);
+        chip8.execute_raw(0xD015).unwrap();
+        assert_eq!(
+            chip8.render_ascii('#', '.').lines().next(),
+            Some(&*format!("#{}", ".".repeat(63)))
+        );
+
+        assert!(matches!(
+            chip8.set_font(&[0; 81]),
+            Err(Chip8Error::FontLoadError { size: 81, .. })
+        ));
+    }
+
+    #[test]
+    fn roms_load_at_a_custom_start_address() {
+        let mut chip8 = Chip8::with_start_address(0x600).unwrap();
+        assert_eq!(chip8.pc(), u12::new(0x600));
+
+        // 6007: V0 = 7
+        chip8.load(&[0x60, 0x07]).unwrap();
+        assert_eq!(chip8.memory_range(0x600..0x602), Some(&[0x60, 0x07][..]));
+        assert_eq!(chip8.memory_range(0x200..0x202), Some(&[0, 0][..]));
+
+        chip8.cpu_cycle().unwrap();
+        assert_eq!(chip8.reg(u4::new(0)), 7);
+        assert_eq!(chip8.pc(), u12::new(0x602));
+        assert_eq!(chip8.free_memory(), MEMORY_SIZE - 0x602);
+    }
+
+    #[test]
+    fn pressed_keys_are_listed_in_order() {
+        let mut chip8 = Chip8::new();
+        assert_eq!(chip8.pressed_keys().count(), 0);
+
+        chip8.set_key(u4::new(0xA), true);
+        chip8.set_key(u4::new(0x3), true);
+        assert_eq!(
+            chip8.pressed_keys().collect::<Vec<_>>(),
+            [u4::new(0x3), u4::new(0xA)]
+        );
+    }
+
+    #[test]
+    fn text_renders_cover_the_display() {
+        // 00E0: clear, 6000: V0 = 0, F029: I = glyph 0, D005: draw it at (0, 0)
+        let mut chip8 = load(&[0x00, 0xE0, 0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05]);
+        for _ in 0..4 {
+            chip8.cpu_cycle().unwrap();
+        }
+
+        let ascii = chip8.render_ascii('#', '.');
+        assert_eq!(ascii.lines().count(), DISPLAY_Y);
+        assert!(ascii.lines().all(|line| line.chars().count() == DISPLAY_X));
+        assert!(ascii.starts_with("####...."));
+
+        let blocks = chip8.render_half_blocks();
+        assert_eq!(blocks.lines().count(), DISPLAY_Y / 2);
+        assert!(blocks.lines().all(|line| line.chars().count() == DISPLAY_X));
+        // Rows F0 and 90 of the glyph
+        assert!(blocks.starts_with("█▀▀█ "));
+    }
+
+    #[test]
+    fn clone_diverges_after_a_step() {
+        let chip8 = load(IBM_LOGO);
+        let mut copy = chip8.clone();
+        assert_eq!(copy, chip8);
+
+        copy.step().unwrap();
+        assert_ne!(copy, chip8);
+        assert_eq!(chip8.pc(), u12::new(0x200));
+        assert_eq!(copy.pc(), u12::new(0x202));
+    }
+
+    #[test]
+    fn formatting_shows_the_registers() {
+        let mut chip8 = load(&[0x6A, 0x12]);
+        chip8.cpu_cycle().unwrap();
+
+        let display = chip8.to_string();
+        assert!(display.starts_with("PC=202 I=000 V0=00"));
+        assert!(display.contains(" VA=12 "));
+        assert!(display.ends_with("DT=00 ST=00 SP=0"));
+
+        let debug = format!("{chip8:?}");
+        assert!(debug.starts_with("Chip8 { pc: 202, i: 000, v: [00, "));
+    }
+
+    #[test]
+    fn execute_raw_runs_words_not_in_memory() {
+        // Memory at PC is zero, which would be an unknown opcode if it were fetched
+        let mut chip8 = Chip8::new();
+        chip8.execute_raw(0x6A42).unwrap();
+        assert_eq!(chip8.reg(u4::new(0xA)), 0x42);
+        assert_eq!(chip8.pc(), u12::new(0x202));
+
+        chip8.execute_raw(0x1300).unwrap();
+        assert_eq!(chip8.pc(), u12::new(0x300));
+        assert!(matches!(
+            chip8.execute_raw(0xFFFF),
+            Err(Chip8Error::UnknownOpcode { opcode: 0xFFFF })
+        ));
+    }
+
+    #[test]
+    fn register_accessors_read_back_what_was_set() {
+        let mut chip8 = Chip8::new();
+        assert_eq!(chip8.pc(), u12::new(0x200));
+
+        chip8.set_reg(u4::new(0xF), 0x99);
+        chip8.set_index(u12::new(0xABC));
+        chip8.set_pc(u12::new(0x3FE));
+        assert_eq!(chip8.reg(u4::new(0xF)), 0x99);
+        assert_eq!(chip8.reg(u4::new(0xE)), 0);
+        assert_eq!(chip8.index(), u12::new(0xABC));
+        assert_eq!(chip8.pc(), u12::new(0x3FE));
+    }
+
+    #[test]
+    fn step_returns_the_executed_opcode() {
+        // 6A07: VA = 7, D015: draw, which waits for the next frame
+        let mut chip8 = load(&[0x6A, 0x07, 0xD0, 0x15]);
+
+        let (opcode, result) = chip8.step().unwrap();
+        assert_eq!(
+            opcode,
+            Opcode::SetRegImm {
+                x: u4::new(0xA),
+                nn: 7
+            }
+        );
+        assert!(matches!(result, Chip8Result::Continue));
+
+        let (opcode, result) = chip8.step().unwrap();
+        assert_eq!(
+            opcode,
+            Opcode::Draw {
+                x: u4::new(0),
+                y: u4::new(1),
+                n: u4::new(5)
+            }
+        );
+        assert!(matches!(result, Chip8Result::WaitForNextFrame));
+    }
+
+    #[test]
+    fn key_change_hook_fires_once_per_change() {
+        let mut chip8 = Chip8::new();
+        let changes = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let sink = changes.clone();
+        chip8.set_on_key_change(Some(Box::new(move |key, pressed| {
+            sink.borrow_mut().push((key, pressed));
+        })));
+
+        chip8.set_key(u4::new(4), true);
+        // Setting the same state again is not a change
+        chip8.set_key(u4::new(4), true);
+        chip8.set_keys(std::array::from_fn(|key| key == 4));
+        assert_eq!(*changes.borrow(), [(u4::new(4), true)]);
+
+        chip8.set_key(u4::new(4), false);
+        assert_eq!(*changes.borrow(), [(u4::new(4), true), (u4::new(4), false)]);
+    }
+
+    #[test]
+    fn memory_range_returns_slices_inside_memory() {
+        let chip8 = load(IBM_LOGO);
+
+        assert_eq!(chip8.memory_range(0x200..0x204), Some(&IBM_LOGO[..4]));
+        assert_eq!(chip8.memory_range(0x50..0x55), Some(&FONT[..5]));
+        assert_eq!(chip8.memory_range(0xF00..0x1100), None);
+        assert_eq!(chip8.memory_range(0x2000..0x2010), None);
+    }
+}
@@ -0,0 +1,194 @@
+use super::{Chip8, MEMORY_SIZE, Opcode};
+use std::collections::BTreeSet;
+
+impl Chip8 {
+    /// Decodes `len` bytes of memory starting at `offset` into instructions.
+    ///
+    /// Returns (address, raw value, decoded opcode) for each 2-byte instruction.
+    /// The range is truncated at the end of memory, and a trailing odd byte is ignored.
+    pub fn disassemble_range(&self, offset: u16, len: u16) -> Vec<(u16, u16, Opcode)> {
+        let start = MEMORY_SIZE.min(usize::from(offset));
+        let end = MEMORY_SIZE.min(start + usize::from(len));
+
+        self.memory[start..end]
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let value = u16::from_be_bytes([chunk[0], chunk[1]]);
+                (offset + i as u16 * 2, value, Opcode::decode(value))
+            })
+            .collect()
+    }
+
+    /// Iterates over the instructions of the loaded ROM as (address, decoded opcode) pairs.
+    ///
+    /// Only the ROM itself is decoded, from the start address to the end of the ROM.
+    /// A trailing odd byte is ignored.
+    pub fn instructions(&self) -> impl Iterator<Item = (u16, Opcode)> + '_ {
+        let start = self.start_address;
+
+        self.memory[start..start + self.rom.len()]
+            .chunks_exact(2)
+            .enumerate()
+            .map(move |(i, chunk)| {
+                let value = u16::from_be_bytes([chunk[0], chunk[1]]);
+                ((start + i * 2) as u16, Opcode::decode(value))
+            })
+    }
+
+    /// Finds instructions in the loaded ROM that this interpreter can't decode.
+    ///
+    /// Returns (address, raw value) for each unknown instruction. This is only a heuristic:
+    /// ROMs mix code and data, so sprites and other data often show up as unknown instructions.
+    pub fn lint_rom(&self) -> Vec<(u16, u16)> {
+        self.instructions()
+            .filter_map(|(addr, opcode)| match opcode {
+                Opcode::Unknown(value) | Opcode::UnknownALU(value) => Some((addr, value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Finds the instruction addresses reachable from the start address.
+    ///
+    /// Follows jumps, calls, returns after calls and both outcomes of skips, so addresses that
+    /// aren't reached are likely data such as sprites. The trace is conservative: it stops at
+    /// computed jumps (Bnnn), whose targets depend on register values, and at unknown opcodes,
+    /// so code only reached that way is missed.
+    pub fn trace_reachable_code(&self) -> BTreeSet<u16> {
+        let mut reachable = BTreeSet::new();
+        let mut pending = vec![self.start_address as u16];
+
+        while let Some(addr) = pending.pop() {
+            if usize::from(addr) + 1 >= MEMORY_SIZE || reachable.contains(&addr) {
+                continue;
+            }
+
+            let index = usize::from(addr);
+            let opcode = Opcode::decode(u16::from_be_bytes([
+                self.memory[index],
+                self.memory[index + 1],
+            ]));
+
+            let next = addr + 2;
+            match opcode {
+                Opcode::Unknown(_) | Opcode::UnknownALU(_) => continue,
+                Opcode::Return | Opcode::JumpWithOffset { .. } => {}
+                Opcode::Jump { nnn } => pending.push(usize::from(nnn) as u16),
+                Opcode::Call { nnn } => pending.extend([usize::from(nnn) as u16, next]),
+                Opcode::SkipRegEqualImm { .. }
+                | Opcode::SkipRegNotEqualImm { .. }
+                | Opcode::SkipRegEqualReg { .. }
+                | Opcode::SkipRegNotEqualReg { .. }
+                | Opcode::SkipIfPressed { .. }
+                | Opcode::SkipIfNotPressed { .. } => pending.extend([next, next + 2]),
+                _ => pending.push(next),
+            }
+            reachable.insert(addr);
+        }
+
+        reachable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{u4, u12};
+
+    #[test]
+    fn zeroed_memory_is_not_code() {
+        // 6001, 0000 (zero padding), 6002
+        let mut chip8 = Chip8::new();
+        chip8.load(&[0x60, 0x01, 0x00, 0x00, 0x60, 0x02]).unwrap();
+
+        assert_eq!(chip8.lint_rom(), [(0x202, 0x0000)]);
+        assert_eq!(chip8.trace_reachable_code(), BTreeSet::from([0x200]));
+    }
+
+    #[test]
+    fn data_between_code_is_not_code() {
+        // 2206: call, 1202: jump to self, F0 90: sprite, 00EE: return
+        let mut chip8 = Chip8::new();
+        chip8
+            .load(&[0x22, 0x06, 0x12, 0x02, 0xF0, 0x90, 0x00, 0xEE])
+            .unwrap();
+
+        assert_eq!(
+            chip8.trace_reachable_code(),
+            BTreeSet::from([0x200, 0x202, 0x206])
+        );
+    }
+
+    #[test]
+    fn lint_finds_the_unknown_instruction() {
+        // 6001, 7001, FFFF (unknown), A300, D015, 120A
+        let mut chip8 = Chip8::new();
+        chip8
+            .load(&[
+                0x60, 0x01, 0x70, 0x01, 0xFF, 0xFF, 0xA3, 0x00, 0xD0, 0x15, 0x12, 0x0A,
+            ])
+            .unwrap();
+
+        assert_eq!(chip8.lint_rom(), [(0x204, 0xFFFF)]);
+    }
+
+    #[test]
+    fn disassembly_stops_at_the_end_of_memory() {
+        // 00E0: clear, 1200: jump
+        let mut chip8 = Chip8::new();
+        chip8.load(&[0x00, 0xE0, 0x12, 0x00]).unwrap();
+
+        let expected = [
+            (0x200, 0x00E0, Opcode::ClearDisplay),
+            (
+                0x202,
+                0x1200,
+                Opcode::Jump {
+                    nnn: u12::new(0x200),
+                },
+            ),
+        ];
+        assert_eq!(chip8.disassemble_range(0x200, 4), expected);
+        // A trailing odd byte is left out
+        assert_eq!(chip8.disassemble_range(0x200, 5), expected);
+
+        chip8.mem_set(0xFFE, 0x60).unwrap();
+        chip8.mem_set(0xFFF, 0x01).unwrap();
+        let tail = chip8.disassemble_range(0xFFC, 0x10);
+        assert_eq!(
+            tail.iter()
+                .map(|(addr, value, _)| (*addr, *value))
+                .collect::<Vec<_>>(),
+            [(0xFFC, 0x0000), (0xFFE, 0x6001)]
+        );
+        assert!(chip8.disassemble_range(0x1000, 2).is_empty());
+    }
+
+    #[test]
+    fn instructions_cover_only_the_rom() {
+        // 00E0: clear, 6A05: VA = 5, 1204: jump to self
+        let mut chip8 = Chip8::new();
+        chip8.load(&[0x00, 0xE0, 0x6A, 0x05, 0x12, 0x04]).unwrap();
+
+        assert_eq!(
+            chip8.instructions().collect::<Vec<_>>(),
+            [
+                (0x200, Opcode::ClearDisplay),
+                (
+                    0x202,
+                    Opcode::SetRegImm {
+                        x: u4::new(0xA),
+                        nn: 5
+                    }
+                ),
+                (
+                    0x204,
+                    Opcode::Jump {
+                        nnn: u12::new(0x204)
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use super::{
-    Chip8, Chip8Error, Chip8Result, DISPLAY_X, DISPLAY_Y, FONT_START_ADDRESS, Opcode, OpcodeALU,
+    BIG_FONT_START_ADDRESS, Chip8, Chip8Error, Chip8Result, DISPLAY_X, DISPLAY_Y, Opcode,
+    OpcodeALU, font_char_address,
 };
 use crate::{u4, u12};
 
@@ -11,20 +12,34 @@ impl Chip8 {
 
         match opcode {
             Opcode::ClearDisplay => {
-                self.display = [[false; DISPLAY_X]; DISPLAY_Y];
+                for plane in self.selected_planes() {
+                    *self.plane_mut(plane) = [0; DISPLAY_Y];
+                }
             }
             Opcode::Jump { nnn } => {
                 self.pc = nnn;
             }
             Opcode::JumpWithOffset { nnn } => {
-                self.pc = nnn.wrapping_add(self.v[0].into());
+                let reg = if self.quirks.jump_vx {
+                    usize::from(nnn) >> 8
+                } else {
+                    0
+                };
+                self.pc = nnn.wrapping_add(self.v[reg].into());
             }
             Opcode::Call { nnn } => {
-                self.stack.push(self.pc);
+                self.stack_push(self.pc)?;
                 self.pc = nnn;
             }
+            Opcode::Sys { nnn } => {
+                // Machine code routines can't run here, so they are skipped unless strict
+                if self.quirks.strict_sys {
+                    let opcode = usize::from(nnn) as u16;
+                    return Err(Chip8Error::UnknownOpcode { opcode });
+                }
+            }
             Opcode::Return => {
-                self.pc = self.stack.pop().ok_or(Chip8Error::StackUnderflow)?;
+                self.pc = self.stack_pop().ok_or(Chip8Error::StackUnderflow)?;
             }
             Opcode::SkipRegEqualImm { x, nn } => {
                 if self.v[x] == nn {
@@ -56,7 +71,7 @@ impl Chip8 {
                 self.execute_alu(x, y, op);
             }
             Opcode::Random { x, nn } => {
-                let rand_byte: u8 = rand::random();
+                let rand_byte = self.rng.next_u8();
                 self.v[x] = rand_byte & nn;
             }
             Opcode::SetIndexImm { nnn } => {
@@ -68,6 +83,18 @@ impl Chip8 {
             Opcode::Draw { x, y, n } => {
                 return self.execute_draw(x, y, n);
             }
+            Opcode::ScrollDown { n } => {
+                let amount = self.scroll_amount(usize::from(n));
+                self.execute_scroll(0, amount as isize);
+            }
+            Opcode::ScrollRight => {
+                let amount = self.scroll_amount(4);
+                self.execute_scroll(amount as isize, 0);
+            }
+            Opcode::ScrollLeft => {
+                let amount = self.scroll_amount(4);
+                self.execute_scroll(-(amount as isize), 0);
+            }
             Opcode::SkipIfPressed { x } => {
                 if self.keypad[self.v[x] as usize & 0x0F] {
                     self.pc = self.pc.wrapping_add(2);
@@ -91,8 +118,11 @@ impl Chip8 {
                 self.sound_timer = self.v[x];
             }
             Opcode::FontChar { x } => {
+                self.i = font_char_address(u4::new(self.v[x] & 0x0F));
+            }
+            Opcode::BigFontChar { x } => {
                 let digit = self.v[x] & 0x0F;
-                self.i = u12::new(FONT_START_ADDRESS as u16 + digit as u16 * 5);
+                self.i = u12::new(BIG_FONT_START_ADDRESS as u16 + digit as u16 * 10);
             }
             Opcode::BCD { x } => {
                 let value = self.v[x];
@@ -102,15 +132,33 @@ impl Chip8 {
             }
             Opcode::StoreRegs { x } => {
                 for reg_index in 0..=usize::from(x) {
-                    self.memory[self.i] = self.v[reg_index];
-                    self.i = self.i.wrapping_add(1);
+                    self.memory[self.i.wrapping_add(reg_index as u16)] = self.v[reg_index];
+                }
+                if self.quirks.memory_increment {
+                    self.i = self.i.wrapping_add(usize::from(x) as u16 + 1);
                 }
             }
             Opcode::LoadRegs { x } => {
                 for reg_index in 0..=usize::from(x) {
-                    self.v[reg_index] = self.memory[self.i];
-                    self.i = self.i.wrapping_add(1);
+                    self.v[reg_index] = self.memory[self.i.wrapping_add(reg_index as u16)];
                 }
+                if self.quirks.memory_increment {
+                    self.i = self.i.wrapping_add(usize::from(x) as u16 + 1);
+                }
+            }
+            Opcode::SelectPlane { mask } => {
+                if !self.quirks.xo_chip {
+                    let opcode = 0xF001 | ((usize::from(mask) as u16) << 8);
+                    return Err(Chip8Error::UnknownOpcode { opcode });
+                }
+                self.plane_mask = usize::from(mask) as u8 & 0b11;
+            }
+            Opcode::SetPitch { x } => {
+                if !self.quirks.xo_chip {
+                    let opcode = 0xF03A | ((usize::from(x) as u16) << 8);
+                    return Err(Chip8Error::UnknownOpcode { opcode });
+                }
+                self.pitch = self.v[x];
             }
             Opcode::Unknown(opcode) => {
                 return Err(Chip8Error::UnknownOpcode { opcode });
@@ -123,20 +171,30 @@ impl Chip8 {
         Ok(Chip8Result::Continue)
     }
 
+    /// Executes an 8xyN ALU operation.
+    ///
+    /// Every operation that touches VF writes it after the result, so when `x` is VF
+    /// the flag is what remains in the register, not the arithmetic result (as on hardware).
     fn execute_alu(&mut self, x: u4, y: u4, op: OpcodeALU) {
         match op {
             OpcodeALU::Set => self.v[x] = self.v[y],
             OpcodeALU::Or => {
                 self.v[x] |= self.v[y];
-                self.v[0xF] = 0;
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
             }
             OpcodeALU::And => {
                 self.v[x] &= self.v[y];
-                self.v[0xF] = 0;
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
             }
             OpcodeALU::Xor => {
                 self.v[x] ^= self.v[y];
-                self.v[0xF] = 0;
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
             }
             OpcodeALU::Add => {
                 let (res, overflow) = self.v[x].overflowing_add(self.v[y]);
@@ -154,49 +212,106 @@ impl Chip8 {
                 self.v[0xF] = if borrow { 0 } else { 1 }; // VF = 1 if no borrow
             }
             OpcodeALU::ShiftRight => {
-                let lsb = self.v[y] & 1;
-                self.v[x] = self.v[y] >> 1;
-                self.v[0xF] = lsb;
+                let src = if self.quirks.shift_vx {
+                    self.v[x]
+                } else {
+                    self.v[y]
+                };
+                self.v[x] = src >> 1;
+                self.v[0xF] = src & 1;
             }
             OpcodeALU::ShiftLeft => {
-                let msb = (self.v[y] >> 7) & 1;
-                self.v[x] = self.v[y] << 1;
-                self.v[0xF] = msb;
+                let src = if self.quirks.shift_vx {
+                    self.v[x]
+                } else {
+                    self.v[y]
+                };
+                self.v[x] = src << 1;
+                self.v[0xF] = (src >> 7) & 1;
             }
         }
     }
 
     /// Draws a sprite at coordinate (Vx, Vy) with height n bytes.
+    ///
+    /// VF is set to 1 if any pixel was turned off, checked per pixel on every selected plane.
+    /// Pixels clipped at the right and bottom edges are never drawn, so they can't collide.
+    /// With the draw_wrap quirk they are drawn on the opposite edge and collide there.
     fn execute_draw(&mut self, x: u4, y: u4, n: u4) -> Result<Chip8Result, Chip8Error> {
         let x_pos = self.v[x] as usize % DISPLAY_X;
         let y_pos = self.v[y] as usize % DISPLAY_Y;
 
-        // Don't draw out of bounds
-        let row_count = std::cmp::min(usize::from(n), DISPLAY_Y - y_pos);
-        let col_count = std::cmp::min(8, DISPLAY_X - x_pos);
+        // Don't draw out of bounds, unless pixels wrap around to the other side
+        let wrap = self.quirks.draw_wrap;
+        let row_count = if wrap {
+            usize::from(n)
+        } else {
+            std::cmp::min(usize::from(n), DISPLAY_Y - y_pos)
+        };
 
         let mut any_erased = false;
-        for row in 0..row_count {
-            let sprite_byte = self.memory[self.i.wrapping_add(row as u16)];
+        let mut sprite_addr = self.i;
+        for plane in self.selected_planes() {
+            // When multiple planes are selected, their sprites follow each other in memory
+            let mut sprite = [0u8; 16];
+            for (row, byte) in sprite.iter_mut().enumerate().take(row_count) {
+                *byte = self.memory[sprite_addr.wrapping_add(row as u16)];
+            }
+            sprite_addr = sprite_addr.wrapping_add(usize::from(n) as u16);
 
-            for col in 0..col_count {
-                // If current sprite bit is non-zero
-                if (sprite_byte & (0x80 >> col)) != 0 {
-                    let pixel = &mut self.display[y_pos + row][x_pos + col];
+            let display = self.plane_mut(plane);
+            for (row, &sprite_byte) in sprite.iter().enumerate().take(row_count) {
+                // Whole rows are flipped at once
+                let mask = sprite_row_mask(sprite_byte, x_pos, wrap);
+                let pixels = &mut display[(y_pos + row) % DISPLAY_Y];
 
-                    // Flip the pixel
-                    *pixel ^= true;
-
-                    // If pixel was turned off, set erased flag
-                    if !*pixel {
-                        any_erased = true;
-                    }
-                }
+                // A pixel was turned off if it was lit and the sprite covers it
+                any_erased |= *pixels & mask != 0;
+                *pixels ^= mask;
             }
         }
 
         self.v[0xF] = if any_erased { 1 } else { 0 };
-        Ok(Chip8Result::WaitForNextFrame)
+
+        if self.quirks.display_wait {
+            Ok(Chip8Result::WaitForNextFrame)
+        } else {
+            Ok(Chip8Result::Continue)
+        }
+    }
+
+    /// Returns the number of pixels to scroll by, taking the half scroll quirk into account.
+    fn scroll_amount(&self, pixels: usize) -> usize {
+        if self.quirks.half_scroll {
+            pixels / 2
+        } else {
+            pixels
+        }
+    }
+
+    /// Shifts the selected display planes by (dx, dy) pixels, vacated pixels are turned off.
+    fn execute_scroll(&mut self, dx: isize, dy: isize) {
+        for plane in self.selected_planes() {
+            let display = self.plane_mut(plane);
+            let old = *display;
+
+            for (y, row) in display.iter_mut().enumerate() {
+                let Some(src_row) = y.checked_add_signed(-dy).and_then(|src_y| old.get(src_y))
+                else {
+                    *row = 0;
+                    continue;
+                };
+
+                // The leftmost pixel is the highest bit, so moving right is a right shift
+                let shift = dx.unsigned_abs() as u32;
+                *row = if dx >= 0 {
+                    src_row.checked_shr(shift)
+                } else {
+                    src_row.checked_shl(shift)
+                }
+                .unwrap_or(0);
+            }
+        }
     }
 
     /// Executes the WaitForKey instruction (Fx0A).
@@ -227,3 +342,367 @@ impl Chip8 {
         Chip8Result::WaitForNextFrame
     }
 }
+
+/// Places a sprite byte at column `x_pos` of a packed row. Pixels past the right edge are
+/// clipped, or wrapped around to the left edge if `wrap` is set.
+fn sprite_row_mask(sprite_byte: u8, x_pos: usize, wrap: bool) -> u64 {
+    let at_left_edge = u64::from(sprite_byte) << (u64::BITS - u8::BITS);
+    if wrap {
+        at_left_edge.rotate_right(x_pos as u32)
+    } else {
+        at_left_edge >> x_pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emu::{DEFAULT_PITCH, Display, Quirks, display_coords};
+
+    /// Where `draw` places sprites in memory.
+    const SPRITE_ADDRESS: u16 = 0x300;
+
+    fn machine(quirks: Quirks) -> Chip8 {
+        let mut chip8 = Chip8::new();
+        chip8.set_quirks(Quirks {
+            display_wait: false,
+            ..quirks
+        });
+        chip8
+    }
+
+    /// Draws `rows` rows at (x, y) with Dxyn and returns VF. `sprite` holds the rows of each
+    /// selected plane in turn.
+    fn draw(chip8: &mut Chip8, x: u8, y: u8, sprite: &[u8], rows: usize) -> u8 {
+        for (offset, &byte) in sprite.iter().enumerate() {
+            chip8.mem_set(SPRITE_ADDRESS + offset as u16, byte).unwrap();
+        }
+        chip8.set_index(u12::new(SPRITE_ADDRESS));
+        chip8.set_reg(u4::new(0), x);
+        chip8.set_reg(u4::new(1), y);
+        chip8.execute_raw(0xD010 | rows as u16).unwrap();
+        chip8.reg(u4::new(0xF))
+    }
+
+    /// Draws a sprite one pixel at a time, returns true if any pixel was turned off.
+    fn reference_draw(
+        display: &mut Display<bool>,
+        x: u8,
+        y: u8,
+        sprite: &[u8],
+        wrap: bool,
+    ) -> bool {
+        let (x_pos, y_pos) = (x as usize % DISPLAY_X, y as usize % DISPLAY_Y);
+        let mut any_erased = false;
+
+        for (row, &byte) in sprite.iter().enumerate() {
+            let y = y_pos + row;
+            if !wrap && y >= DISPLAY_Y {
+                break;
+            }
+
+            for bit in 0..8 {
+                let x = x_pos + bit;
+                if byte & (0x80 >> bit) == 0 || (!wrap && x >= DISPLAY_X) {
+                    continue;
+                }
+
+                let pixel = &mut display[y % DISPLAY_Y][x % DISPLAY_X];
+                any_erased |= *pixel;
+                *pixel = !*pixel;
+            }
+        }
+
+        any_erased
+    }
+
+    #[test]
+    fn packed_draw_matches_per_pixel_draw() {
+        let positions = [
+            (0, 0),
+            (1, 3),
+            (7, 28),
+            (30, 12),
+            (56, 31),
+            (57, 20),
+            (60, 26),
+            (63, 0),
+            (67, 35),
+            (255, 255),
+            (59, 29),
+            (0, 31),
+        ];
+
+        for draw_wrap in [false, true] {
+            let mut chip8 = machine(Quirks {
+                draw_wrap,
+                ..Quirks::default()
+            });
+            let mut expected = [[false; DISPLAY_X]; DISPLAY_Y];
+
+            // Draw everything twice, so the second pass erases the first and collides
+            for (i, &(x, y)) in positions.iter().chain(&positions).enumerate() {
+                let sprite: Vec<u8> = (0..1 + i % 15)
+                    .map(|row| (i * 37 + row * 11) as u8 | 0x81)
+                    .collect();
+
+                let collision = reference_draw(&mut expected, x, y, &sprite, draw_wrap);
+                let vf = draw(&mut chip8, x, y, &sprite, sprite.len());
+                assert_eq!(vf, u8::from(collision));
+                assert_eq!(chip8.display(), expected, "draw {i} at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn packed_planes_match_per_pixel_draw() {
+        let mut chip8 = machine(Quirks {
+            xo_chip: true,
+            ..Quirks::default()
+        });
+        let mut expected = [[[false; DISPLAY_X]; DISPLAY_Y]; 2];
+
+        for (i, (x, y)) in [(2, 2), (5, 4), (60, 30), (3, 1), (61, 29)]
+            .into_iter()
+            .enumerate()
+        {
+            // Fn01 selects the planes in the bitmask n, cycling through 1, 2 and 3
+            let plane_mask = i % 3 + 1;
+            chip8
+                .execute_raw(0xF001 | (plane_mask as u16) << 8)
+                .unwrap();
+
+            let rows = 4;
+            let planes: Vec<usize> = (0..2).filter(|p| plane_mask & (1 << p) != 0).collect();
+            let sprite: Vec<u8> = (0..rows * planes.len())
+                .map(|row| (i * 53 + row * 29) as u8 | 0x18)
+                .collect();
+
+            let mut collision = false;
+            for (plane, plane_sprite) in planes.iter().zip(sprite.chunks(rows)) {
+                collision |= reference_draw(&mut expected[*plane], x, y, plane_sprite, false);
+            }
+            assert_eq!(draw(&mut chip8, x, y, &sprite, rows), u8::from(collision));
+
+            for (y, x) in display_coords() {
+                let planes = u8::from(expected[0][y][x]) | u8::from(expected[1][y][x]) << 1;
+                assert_eq!(chip8.get_display_planes(y, x), planes, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    /// Sets VF and VE, executes `word` and returns VF.
+    fn alu_vf(quirks: Quirks, word: u16, vf: u8, v0: u8, ve: u8) -> u8 {
+        let mut chip8 = machine(quirks);
+        chip8.set_reg(u4::new(0xF), vf);
+        chip8.set_reg(u4::new(0x0), v0);
+        chip8.set_reg(u4::new(0xE), ve);
+        chip8.execute_raw(word).unwrap();
+        chip8.reg(u4::new(0xF))
+    }
+
+    #[test]
+    fn add_to_vf_keeps_carry() {
+        let quirks = Quirks::default();
+        // 0xFF + 0x01 wraps to 0x00, the carry remains
+        assert_eq!(alu_vf(quirks, 0x8F04, 0xFF, 0x01, 0), 1);
+        // 0x10 + 0x20 = 0x30 is lost, the cleared carry remains
+        assert_eq!(alu_vf(quirks, 0x8F04, 0x10, 0x20, 0), 0);
+    }
+
+    #[test]
+    fn sub_from_vf_keeps_borrow_flag() {
+        let quirks = Quirks::default();
+        // 8F05: VF = VF - V0, the flag is 1 when there's no borrow
+        assert_eq!(alu_vf(quirks, 0x8F05, 0x30, 0x10, 0), 1);
+        assert_eq!(alu_vf(quirks, 0x8F05, 0x10, 0x20, 0), 0);
+        // 8FE5: VF = VF - VE
+        assert_eq!(alu_vf(quirks, 0x8FE5, 0x05, 0, 0x07), 0);
+        assert_eq!(alu_vf(quirks, 0x8FE5, 0x07, 0, 0x05), 1);
+    }
+
+    #[test]
+    fn shift_into_vf_keeps_shifted_out_bit() {
+        // 8F06 stores V0 >> 1 in VF, then the bit shifted out of V0
+        assert_eq!(alu_vf(Quirks::default(), 0x8F06, 0x00, 0x03, 0), 1);
+        assert_eq!(alu_vf(Quirks::default(), 0x8F06, 0xFF, 0x02, 0), 0);
+
+        // With the shift_vx quirk VF itself is shifted
+        let shift_vx = Quirks {
+            shift_vx: true,
+            ..Quirks::default()
+        };
+        assert_eq!(alu_vf(shift_vx, 0x8F06, 0x02, 0xFF, 0), 0);
+        assert_eq!(alu_vf(shift_vx, 0x8F06, 0x03, 0x00, 0), 1);
+    }
+
+    #[test]
+    fn logic_on_vf_keeps_reset_flag() {
+        // 8F01: VF |= V0, then the vf_reset quirk clears VF
+        assert_eq!(alu_vf(Quirks::default(), 0x8F01, 0x0F, 0xF0, 0), 0);
+
+        let no_reset = Quirks {
+            vf_reset: false,
+            ..Quirks::default()
+        };
+        assert_eq!(alu_vf(no_reset, 0x8F01, 0x0F, 0xF0, 0), 0xFF);
+    }
+
+    #[test]
+    fn clipped_pixels_dont_collide() {
+        let mut chip8 = machine(Quirks::default());
+        // Light the pixels a wrapping sprite would reach: the left edge and the top row
+        draw(&mut chip8, 0, 10, &[0x80], 1);
+        draw(&mut chip8, 20, 0, &[0x80], 1);
+
+        // The fifth pixel would wrap to x = 0, but is clipped
+        assert_eq!(draw(&mut chip8, 60, 10, &[0xF8], 1), 0);
+        // Two rows sticking out of the bottom edge
+        assert_eq!(draw(&mut chip8, 20, 31, &[0x80, 0x80], 2), 0);
+
+        assert!(chip8.get_display_pixel(10, 0));
+        assert!(chip8.get_display_pixel(0, 20));
+    }
+
+    #[test]
+    fn wrapped_pixels_collide() {
+        let mut chip8 = machine(Quirks {
+            draw_wrap: true,
+            ..Quirks::default()
+        });
+        draw(&mut chip8, 0, 10, &[0x80], 1);
+        draw(&mut chip8, 20, 0, &[0x80], 1);
+
+        // The fifth pixel wraps to x = 0 and erases the lit pixel there
+        assert_eq!(draw(&mut chip8, 60, 10, &[0xF8], 1), 1);
+        // The second row wraps to y = 0
+        assert_eq!(draw(&mut chip8, 20, 31, &[0x80, 0x80], 2), 1);
+
+        assert!(!chip8.get_display_pixel(10, 0));
+        assert!(!chip8.get_display_pixel(0, 20));
+        assert!(chip8.get_display_pixel(10, 63));
+        assert!(chip8.get_display_pixel(31, 20));
+    }
+
+    #[test]
+    fn sys_is_ignored() {
+        let mut chip8 = machine(Quirks::default());
+        chip8.execute_raw(0x0123).unwrap();
+        assert_eq!(chip8.pc(), u12::new(0x202));
+
+        let mut strict = machine(Quirks {
+            strict_sys: true,
+            ..Quirks::default()
+        });
+        assert!(matches!(
+            strict.execute_raw(0x0123),
+            Err(Chip8Error::UnknownOpcode { .. })
+        ));
+    }
+
+    #[test]
+    fn zero_word_is_unknown() {
+        let mut chip8 = machine(Quirks::default());
+        assert!(matches!(
+            chip8.execute_raw(0x0000),
+            Err(Chip8Error::UnknownOpcode { opcode: 0x0000 })
+        ));
+    }
+
+    #[test]
+    fn scrolls_shift_packed_rows() {
+        let mut chip8 = machine(Quirks::default());
+        draw(&mut chip8, 8, 2, &[0xFF], 1);
+        assert_eq!(chip8.display_rows_packed()[2], 0x00FF_0000_0000_0000);
+
+        // 00C3: scroll down 3 rows
+        chip8.execute_raw(0x00C3).unwrap();
+        assert_eq!(chip8.display_rows_packed()[2], 0);
+        assert_eq!(chip8.display_rows_packed()[5], 0x00FF_0000_0000_0000);
+
+        // 00FB: scroll right 4 pixels, 00FC: scroll left 4 pixels
+        chip8.execute_raw(0x00FB).unwrap();
+        assert_eq!(chip8.display_rows_packed()[5], 0x000F_F000_0000_0000);
+        chip8.execute_raw(0x00FC).unwrap();
+        chip8.execute_raw(0x00FC).unwrap();
+        assert_eq!(chip8.display_rows_packed()[5], 0x0FF0_0000_0000_0000);
+
+        // Pixels scrolled past the left edge are gone
+        chip8.execute_raw(0x00FC).unwrap();
+        chip8.execute_raw(0x00FC).unwrap();
+        assert_eq!(chip8.display_rows_packed()[5], 0xF000_0000_0000_0000);
+    }
+
+    #[test]
+    fn half_scroll_moves_half_as_far() {
+        let mut chip8 = machine(Quirks {
+            half_scroll: true,
+            ..Quirks::default()
+        });
+        draw(&mut chip8, 8, 2, &[0xFF], 1);
+
+        // 00C4: scroll down 4 rows, halved to 2
+        chip8.execute_raw(0x00C4).unwrap();
+        assert_eq!(chip8.display_rows_packed()[4], 0x00FF_0000_0000_0000);
+
+        chip8.execute_raw(0x00FB).unwrap();
+        assert_eq!(chip8.display_rows_packed()[4], 0x003F_C000_0000_0000);
+        chip8.execute_raw(0x00FC).unwrap();
+        assert_eq!(chip8.display_rows_packed()[4], 0x00FF_0000_0000_0000);
+    }
+
+    #[test]
+    fn second_plane_draw_leaves_first_plane_alone() {
+        let mut chip8 = machine(Quirks {
+            xo_chip: true,
+            ..Quirks::default()
+        });
+        draw(&mut chip8, 0, 0, &[0xF0], 1);
+        let first_plane = chip8.display;
+
+        // F201: select only the second plane
+        chip8.execute_raw(0xF201).unwrap();
+        assert_eq!(draw(&mut chip8, 2, 0, &[0xFF], 1), 0);
+
+        assert_eq!(chip8.display, first_plane);
+        assert_eq!(chip8.display2[0], 0x3FC0_0000_0000_0000);
+        assert_eq!(chip8.get_display_planes(0, 2), 0b11);
+        assert_eq!(chip8.get_display_planes(0, 5), 0b10);
+    }
+
+    #[test]
+    fn clip_mode_draw_keeps_only_the_visible_columns() {
+        let mut chip8 = machine(Quirks::default());
+        draw(&mut chip8, 62, 0, &[0xFF], 1);
+        assert_eq!(chip8.display_rows_packed()[0], 0b11);
+
+        let mut chip8 = machine(Quirks {
+            draw_wrap: true,
+            ..Quirks::default()
+        });
+        draw(&mut chip8, 62, 0, &[0xFF], 1);
+        assert_eq!(chip8.display_rows_packed()[0], 0xFC00_0000_0000_0003);
+    }
+
+    #[test]
+    fn set_pitch_needs_xo_chip() {
+        // F23A: pitch = V2
+        let mut chip8 = machine(Quirks::default());
+        chip8.set_reg(u4::new(2), 112);
+        assert!(matches!(
+            chip8.execute_raw(0xF23A),
+            Err(Chip8Error::UnknownOpcode { opcode: 0xF23A })
+        ));
+        assert_eq!(chip8.sound_pitch(), DEFAULT_PITCH);
+
+        let mut chip8 = machine(Quirks {
+            xo_chip: true,
+            ..Quirks::default()
+        });
+        chip8.set_reg(u4::new(2), 112);
+        chip8.execute_raw(0xF23A).unwrap();
+        assert_eq!(chip8.sound_pitch(), 112);
+        // 48 steps above the default is one octave up
+        assert_eq!(chip8.sound_pitch_ratio(), 2.0);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{u4, u12};
+
 /// The default CHIP-8 font set.
 ///
 /// Taken from https://tobiasvl.github.io/blog/write-a-chip-8-emulator/#font
@@ -20,7 +22,57 @@ pub const FONT: [u8; 16 * 5] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+/// The SUPER-CHIP high resolution font set, with 8x10 pixel glyphs.
+///
+/// Taken from https://github.com/JohnEarnest/Octo
+pub const BIG_FONT: [u8; 16 * 10] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
 // Memory addresses where the font data should be loaded.
 // These are specified by the CHIP-8 specification.
 pub const FONT_START_ADDRESS: usize = 0x50;
 pub const FONT_END_ADDRESS: usize = FONT_START_ADDRESS + FONT.len();
+/// Size in bytes of a single character in the default font.
+pub const FONT_CHAR_SIZE: usize = 5;
+
+/// Returns the address of the default font sprite for a hex digit, as set by Fx29.
+pub fn font_char_address(digit: u4) -> u12 {
+    u12::new((FONT_START_ADDRESS + usize::from(digit) * FONT_CHAR_SIZE) as u16)
+}
+
+// The high resolution font is placed directly after the default font.
+pub const BIG_FONT_START_ADDRESS: usize = FONT_END_ADDRESS;
+pub const BIG_FONT_END_ADDRESS: usize = BIG_FONT_START_ADDRESS + BIG_FONT.len();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyph_a_follows_the_ten_digits() {
+        let address = font_char_address(u4::new(0xA));
+        assert_eq!(address, u12::new(0x82));
+
+        let start = usize::from(address) - FONT_START_ADDRESS;
+        assert_eq!(
+            FONT[start..start + FONT_CHAR_SIZE],
+            [0xF0, 0x90, 0xF0, 0x90, 0x90]
+        );
+    }
+}
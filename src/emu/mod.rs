@@ -1,12 +1,26 @@
+//! The CHIP-8 emulator core.
+//!
+//! This module only depends on the standard library and, through the default `rand` feature,
+//! on `rand`. It doesn't read the clock: time is passed in by the caller as `dt`. Building with
+//! `--no-default-features` swaps `rand` for a built-in generator and leaves out the window,
+//! audio and terminal dependencies of the front-ends. Nothing here touches the filesystem,
+//! ROMs are passed in as bytes, so the core also builds for `wasm32-unknown-unknown`.
+
+mod builder;
 mod chip8;
+mod disasm;
 mod execute;
 mod font;
 mod opcode;
+mod quirks;
+mod rng;
 mod runner;
 mod types;
 
+pub use builder::*;
 pub use chip8::*;
 pub use font::*;
 pub use opcode::*;
+pub use quirks::*;
 pub use runner::*;
 pub use types::*;
@@ -1,9 +1,11 @@
+use std::fmt;
+
 use crate::{u4, u12};
 
 /// CHIP-8 instruction opcodes.
 ///
 /// The fields (x, y, n, nn, nnn) correspond to the operands encoded in the opcode.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Opcode {
     /// 1nnn - Jump to location nnn.
     Jump { nnn: u12 },
@@ -13,6 +15,9 @@ pub enum Opcode {
     Call { nnn: u12 },
     /// 00EE - Return from a subroutine.
     Return,
+    /// 0nnn - Call a machine code routine at nnn. Ignored by most interpreters.
+    /// 0000 is never decoded as this, as running into zeroed memory is almost always a bug.
+    Sys { nnn: u12 },
 
     /// 3xnn - Skip next instruction if Vx == nn.
     SkipRegEqualImm { x: u4, nn: u8 },
@@ -41,6 +46,14 @@ pub enum Opcode {
     ClearDisplay,
     /// Dxyn - Display sprite.
     Draw { x: u4, y: u4, n: u4 },
+    /// 00Cn - Scroll the display down by n pixels (SUPER-CHIP).
+    ScrollDown { n: u4 },
+    /// 00FB - Scroll the display right by 4 pixels (SUPER-CHIP).
+    ScrollRight,
+    /// 00FC - Scroll the display left by 4 pixels (SUPER-CHIP).
+    ScrollLeft,
+    /// Fn01 - Select the display planes affected by drawing (XO-CHIP).
+    SelectPlane { mask: u4 },
 
     /// Ex9E - Skip next instruction if key with the value of Vx is pressed.
     SkipIfPressed { x: u4 },
@@ -56,8 +69,13 @@ pub enum Opcode {
     /// Fx18 - Set sound timer = Vx.
     SetSoundTimer { x: u4 },
 
+    /// Fx3A - Set the audio pitch to Vx (XO-CHIP).
+    SetPitch { x: u4 },
+
     /// Fx29 - Set I = location of sprite for digit Vx.
     FontChar { x: u4 },
+    /// Fx30 - Set I = location of high resolution sprite for digit Vx (SUPER-CHIP).
+    BigFontChar { x: u4 },
     /// Fx33 - Store BCD representation of Vx in memory locations I, I+1, and I+2.
     BCD { x: u4 },
 
@@ -73,7 +91,7 @@ pub enum Opcode {
 }
 
 /// ALU operations for the 8xyN instruction.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum OpcodeALU {
     /// 8xy0 - Vx = Vy
     Set,
@@ -96,6 +114,75 @@ pub enum OpcodeALU {
 }
 
 impl Opcode {
+    /// Returns the name of the instruction, without its operands.
+    ///
+    /// ALU operations are named after the operation, e.g. `ALU::Add`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Opcode::Jump { .. } => "Jump",
+            Opcode::JumpWithOffset { .. } => "JumpWithOffset",
+            Opcode::Call { .. } => "Call",
+            Opcode::Return => "Return",
+            Opcode::Sys { .. } => "Sys",
+            Opcode::SkipRegEqualImm { .. } => "SkipRegEqualImm",
+            Opcode::SkipRegNotEqualImm { .. } => "SkipRegNotEqualImm",
+            Opcode::SkipRegEqualReg { .. } => "SkipRegEqualReg",
+            Opcode::SkipRegNotEqualReg { .. } => "SkipRegNotEqualReg",
+            Opcode::SetRegImm { .. } => "SetRegImm",
+            Opcode::AddRegImm { .. } => "AddRegImm",
+            Opcode::SetIndexImm { .. } => "SetIndexImm",
+            Opcode::AddIndexReg { .. } => "AddIndexReg",
+            Opcode::ALU { op, .. } => match op {
+                OpcodeALU::Set => "ALU::Set",
+                OpcodeALU::Or => "ALU::Or",
+                OpcodeALU::And => "ALU::And",
+                OpcodeALU::Xor => "ALU::Xor",
+                OpcodeALU::Add => "ALU::Add",
+                OpcodeALU::Sub => "ALU::Sub",
+                OpcodeALU::ShiftRight => "ALU::ShiftRight",
+                OpcodeALU::SubReverse => "ALU::SubReverse",
+                OpcodeALU::ShiftLeft => "ALU::ShiftLeft",
+            },
+            Opcode::Random { .. } => "Random",
+            Opcode::ClearDisplay => "ClearDisplay",
+            Opcode::Draw { .. } => "Draw",
+            Opcode::ScrollDown { .. } => "ScrollDown",
+            Opcode::ScrollRight => "ScrollRight",
+            Opcode::ScrollLeft => "ScrollLeft",
+            Opcode::SelectPlane { .. } => "SelectPlane",
+            Opcode::SkipIfPressed { .. } => "SkipIfPressed",
+            Opcode::SkipIfNotPressed { .. } => "SkipIfNotPressed",
+            Opcode::WaitForKey { .. } => "WaitForKey",
+            Opcode::ReadDelayTimer { .. } => "ReadDelayTimer",
+            Opcode::SetDelayTimer { .. } => "SetDelayTimer",
+            Opcode::SetSoundTimer { .. } => "SetSoundTimer",
+            Opcode::SetPitch { .. } => "SetPitch",
+            Opcode::FontChar { .. } => "FontChar",
+            Opcode::BigFontChar { .. } => "BigFontChar",
+            Opcode::BCD { .. } => "BCD",
+            Opcode::StoreRegs { .. } => "StoreRegs",
+            Opcode::LoadRegs { .. } => "LoadRegs",
+            Opcode::Unknown(_) => "Unknown",
+            Opcode::UnknownALU(_) => "UnknownALU",
+        }
+    }
+
+    /// Approximate time the instruction takes on the COSMAC VIP, in multiples of the time
+    /// taken by the simplest instructions. Used by the runner's accurate timing mode.
+    pub fn cost(&self) -> u32 {
+        match self {
+            // Clearing and scrolling touch the whole display
+            Opcode::ClearDisplay | Opcode::ScrollDown { .. } => 4,
+            Opcode::ScrollRight | Opcode::ScrollLeft => 4,
+            // Each sprite row is read and drawn separately
+            Opcode::Draw { n, .. } => 2 + usize::from(*n) as u32,
+            Opcode::StoreRegs { x } | Opcode::LoadRegs { x } => 2 + usize::from(*x) as u32 / 2,
+            Opcode::BCD { .. } => 4,
+            Opcode::Call { .. } | Opcode::Return => 2,
+            _ => 1,
+        }
+    }
+
     /// Decode a 16-bit raw opcode into an `Opcode` enum variant.
     pub fn decode(opcode: u16) -> Self {
         let nibble = (
@@ -114,6 +201,12 @@ impl Opcode {
         match (nibble.0, nibble.1, nibble.2, nibble.3) {
             (0x0, 0x0, 0xE, 0x0) => Opcode::ClearDisplay,
             (0x0, 0x0, 0xE, 0xE) => Opcode::Return,
+            (0x0, 0x0, 0xC, _) => Opcode::ScrollDown { n },
+            (0x0, 0x0, 0xF, 0xB) => Opcode::ScrollRight,
+            (0x0, 0x0, 0xF, 0xC) => Opcode::ScrollLeft,
+            // Zeroed memory stays unknown, so a runaway PC errors instead of sliding through it
+            (0x0, 0x0, 0x0, 0x0) => Opcode::Unknown(opcode),
+            (0x0, _, _, _) => Opcode::Sys { nnn },
             (0x1, _, _, _) => Opcode::Jump { nnn },
             (0x2, _, _, _) => Opcode::Call { nnn },
             (0x3, _, _, _) => Opcode::SkipRegEqualImm { x, nn },
@@ -144,13 +237,16 @@ impl Opcode {
             (0xD, _, _, _) => Opcode::Draw { x, y, n },
             (0xE, _, 0x9, 0xE) => Opcode::SkipIfPressed { x },
             (0xE, _, 0xA, 0x1) => Opcode::SkipIfNotPressed { x },
+            (0xF, _, 0x0, 0x1) => Opcode::SelectPlane { mask: x },
             (0xF, _, 0x0, 0xA) => Opcode::WaitForKey { x },
             (0xF, _, 0x0, 0x7) => Opcode::ReadDelayTimer { x },
             (0xF, _, 0x1, 0x5) => Opcode::SetDelayTimer { x },
             (0xF, _, 0x1, 0x8) => Opcode::SetSoundTimer { x },
             (0xF, _, 0x1, 0xE) => Opcode::AddIndexReg { x },
             (0xF, _, 0x2, 0x9) => Opcode::FontChar { x },
+            (0xF, _, 0x3, 0x0) => Opcode::BigFontChar { x },
             (0xF, _, 0x3, 0x3) => Opcode::BCD { x },
+            (0xF, _, 0x3, 0xA) => Opcode::SetPitch { x },
             (0xF, _, 0x5, 0x5) => Opcode::StoreRegs { x },
             (0xF, _, 0x6, 0x5) => Opcode::LoadRegs { x },
 
@@ -158,3 +254,75 @@ impl Opcode {
         }
     }
 }
+
+/// Formats the instruction as an assembly mnemonic, e.g. `LD V1, 0A`. All values are in hex.
+///
+/// Unknown instructions are shown as raw data words, e.g. `DW F0FF`.
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Opcode::Jump { nnn } => write!(f, "JP {nnn:03X}"),
+            Opcode::JumpWithOffset { nnn } => write!(f, "JP V0, {nnn:03X}"),
+            Opcode::Call { nnn } => write!(f, "CALL {nnn:03X}"),
+            Opcode::Return => write!(f, "RET"),
+            Opcode::Sys { nnn } => write!(f, "SYS {nnn:03X}"),
+            Opcode::SkipRegEqualImm { x, nn } => write!(f, "SE V{x:X}, {nn:02X}"),
+            Opcode::SkipRegNotEqualImm { x, nn } => write!(f, "SNE V{x:X}, {nn:02X}"),
+            Opcode::SkipRegEqualReg { x, y } => write!(f, "SE V{x:X}, V{y:X}"),
+            Opcode::SkipRegNotEqualReg { x, y } => write!(f, "SNE V{x:X}, V{y:X}"),
+            Opcode::SetRegImm { x, nn } => write!(f, "LD V{x:X}, {nn:02X}"),
+            Opcode::AddRegImm { x, nn } => write!(f, "ADD V{x:X}, {nn:02X}"),
+            Opcode::SetIndexImm { nnn } => write!(f, "LD I, {nnn:03X}"),
+            Opcode::AddIndexReg { x } => write!(f, "ADD I, V{x:X}"),
+            Opcode::ALU { x, y, op } => {
+                let mnemonic = match op {
+                    OpcodeALU::Set => "LD",
+                    OpcodeALU::Or => "OR",
+                    OpcodeALU::And => "AND",
+                    OpcodeALU::Xor => "XOR",
+                    OpcodeALU::Add => "ADD",
+                    OpcodeALU::Sub => "SUB",
+                    OpcodeALU::ShiftRight => "SHR",
+                    OpcodeALU::SubReverse => "SUBN",
+                    OpcodeALU::ShiftLeft => "SHL",
+                };
+                write!(f, "{mnemonic} V{x:X}, V{y:X}")
+            }
+            Opcode::Random { x, nn } => write!(f, "RND V{x:X}, {nn:02X}"),
+            Opcode::ClearDisplay => write!(f, "CLS"),
+            Opcode::Draw { x, y, n } => write!(f, "DRW V{x:X}, V{y:X}, {n:X}"),
+            Opcode::ScrollDown { n } => write!(f, "SCD {n:X}"),
+            Opcode::ScrollRight => write!(f, "SCR"),
+            Opcode::ScrollLeft => write!(f, "SCL"),
+            Opcode::SelectPlane { mask } => write!(f, "PLANE {mask:X}"),
+            Opcode::SkipIfPressed { x } => write!(f, "SKP V{x:X}"),
+            Opcode::SkipIfNotPressed { x } => write!(f, "SKNP V{x:X}"),
+            Opcode::WaitForKey { x } => write!(f, "LD V{x:X}, K"),
+            Opcode::ReadDelayTimer { x } => write!(f, "LD V{x:X}, DT"),
+            Opcode::SetDelayTimer { x } => write!(f, "LD DT, V{x:X}"),
+            Opcode::SetSoundTimer { x } => write!(f, "LD ST, V{x:X}"),
+            Opcode::SetPitch { x } => write!(f, "PITCH V{x:X}"),
+            Opcode::FontChar { x } => write!(f, "LD F, V{x:X}"),
+            Opcode::BigFontChar { x } => write!(f, "LD HF, V{x:X}"),
+            Opcode::BCD { x } => write!(f, "LD B, V{x:X}"),
+            Opcode::StoreRegs { x } => write!(f, "LD [I], V{x:X}"),
+            Opcode::LoadRegs { x } => write!(f, "LD V{x:X}, [I]"),
+            Opcode::Unknown(opcode) | Opcode::UnknownALU(opcode) => write!(f, "DW {opcode:04X}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_costs_more_than_setting_a_register() {
+        let set = Opcode::decode(0x6A12);
+        assert_eq!(set.cost(), 1);
+
+        let draw = Opcode::decode(0xD015);
+        assert!(draw.cost() > set.cost());
+        assert!(Opcode::decode(0xD01F).cost() > draw.cost());
+    }
+}
@@ -0,0 +1,73 @@
+/// Toggles for instruction behaviors that differ between CHIP-8 interpreters.
+///
+/// The defaults match the original COSMAC VIP interpreter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    /// 8xy1, 8xy2 and 8xy3 reset VF to 0.
+    pub vf_reset: bool,
+    /// Fx55 and Fx65 increment I by x + 1.
+    pub memory_increment: bool,
+    /// 8xy6 and 8xyE shift Vx in place instead of storing Vy shifted into Vx.
+    pub shift_vx: bool,
+    /// Bnnn jumps to nnn + Vx (where x is the highest nibble of nnn) instead of nnn + V0.
+    pub jump_vx: bool,
+    /// 00CN, 00FB and 00FC scroll by half the amount in low resolution mode, as in SUPER-CHIP 1.1.
+    pub half_scroll: bool,
+    /// Dxyn wraps sprite pixels around the display edges instead of clipping them.
+    pub draw_wrap: bool,
+    /// Dxyn waits for the next frame before continuing, limiting draws to one per frame.
+    pub display_wait: bool,
+    /// 0nnn raises an unknown opcode error instead of being ignored.
+    pub strict_sys: bool,
+    /// Fetching an instruction from an odd address raises an error, catching bad jumps.
+    pub align_pc: bool,
+    /// Enables the XO-CHIP second display plane and the Fn01 plane select instruction.
+    pub xo_chip: bool,
+}
+
+/// A ROM identified by the FNV-1a hash of its contents (see `Chip8::rom_hash`).
+pub(crate) struct KnownRom {
+    pub hash: u64,
+    pub quirks: Quirks,
+}
+
+/// ROMs with known quirk requirements.
+///
+/// Entries are added by hashing a verified copy of the ROM with `Chip8::rom_hash`.
+pub(crate) const KNOWN_ROMS: &[KnownRom] = &[
+    // IBM Logo.ch8 (132 bytes)
+    KnownRom {
+        hash: 0x64E4_5391_BA02_38A1,
+        quirks: Quirks::COSMAC_VIP,
+    },
+    // roms/wrap_test.ch8 (25 bytes), shows 1 when sprites wrap at the screen edge and 0 otherwise
+    KnownRom {
+        hash: 0xBFF4_6B64_090C_AB0F,
+        quirks: Quirks {
+            draw_wrap: true,
+            ..Quirks::COSMAC_VIP
+        },
+    },
+];
+
+impl Quirks {
+    /// The behavior of the original COSMAC VIP interpreter.
+    pub const COSMAC_VIP: Quirks = Quirks {
+        vf_reset: true,
+        memory_increment: true,
+        shift_vx: false,
+        jump_vx: false,
+        half_scroll: false,
+        draw_wrap: false,
+        display_wait: true,
+        strict_sys: false,
+        align_pc: false,
+        xo_chip: false,
+    };
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::COSMAC_VIP
+    }
+}
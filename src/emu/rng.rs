@@ -0,0 +1,61 @@
+//! Random number source for the Cxnn instruction.
+//!
+//! With the `rand` feature (enabled by default) this wraps `StdRng`. Without it a small
+//! xorshift generator is used instead, so the emulator core has no dependency on `rand`.
+
+#[cfg(feature = "rand")]
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// Random number generator used by the Cxnn instruction.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Chip8Rng {
+    #[cfg(feature = "rand")]
+    inner: StdRng,
+    #[cfg(not(feature = "rand"))]
+    state: u64,
+}
+
+#[cfg(feature = "rand")]
+impl Chip8Rng {
+    /// Creates a generator seeded from the operating system.
+    pub(crate) fn from_entropy() -> Self {
+        Self {
+            inner: StdRng::from_os_rng(),
+        }
+    }
+
+    /// Creates a deterministic generator.
+    pub(crate) fn from_seed(seed: u64) -> Self {
+        Self {
+            inner: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub(crate) fn next_u8(&mut self) -> u8 {
+        self.inner.random()
+    }
+}
+
+#[cfg(not(feature = "rand"))]
+impl Chip8Rng {
+    /// Creates a generator seeded from the randomly keyed hasher in the standard library.
+    pub(crate) fn from_entropy() -> Self {
+        use std::hash::{BuildHasher, RandomState};
+
+        Self::from_seed(RandomState::new().hash_one(0u64))
+    }
+
+    /// Creates a deterministic generator.
+    pub(crate) fn from_seed(seed: u64) -> Self {
+        // Xorshift gets stuck on a zero state
+        Self { state: seed.max(1) }
+    }
+
+    pub(crate) fn next_u8(&mut self) -> u8 {
+        // xorshift64* (Vigna), the high byte has the best statistical quality
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+}
@@ -2,37 +2,107 @@ use super::{Chip8, Chip8Error, Chip8Result};
 use crate::{u4, u12};
 use std::collections::HashSet;
 
-const CPU_HZ: f32 = 700.0;
+/// The default CPU clock speed in instructions per second.
+pub const DEFAULT_CPU_HZ: f32 = 700.0;
+/// The default largest time step processed by a single update, in seconds.
+pub const DEFAULT_MAX_DT: f32 = 0.1;
 const TIMER_HZ: f32 = 60.0;
 
-const CPU_TIME_STEP: f32 = 1.0 / CPU_HZ;
 const TIMER_TIME_STEP: f32 = 1.0 / TIMER_HZ;
 
+/// Decides whether execution should stop after an instruction.
+pub trait Breakpoints {
+    /// Returns true if execution should stop with the machine in its current state.
+    fn should_break(&self, chip8: &Chip8) -> bool;
+}
+
+impl Breakpoints for HashSet<u12> {
+    fn should_break(&self, chip8: &Chip8) -> bool {
+        self.contains(&chip8.pc)
+    }
+}
+
 /// High-level emulator runner that manages timing internally.
 pub struct Chip8Runner {
     chip8: Chip8,
+    cpu_time_step: f32,
+    max_dt: f32,
+    /// Stop running CPU cycles while the machine is in a jump-to-self loop
+    skip_idle_loops: bool,
+    /// Charge each instruction its `Opcode::cost` in CPU cycles instead of a single cycle
+    accurate_timing: bool,
     cpu_dt_accumulator: f32,
     timer_dt_accumulator: f32,
+    /// Keypad state recorded every timer frame, as bitmasks (bit n = key n)
+    recording: Option<Vec<u16>>,
+    /// Keypad state set while recording, applied at the next timer frame
+    pending_keys: Option<[bool; 16]>,
+    /// Keypad states being replayed and the index of the next one to apply
+    replay: Option<(Vec<u16>, usize)>,
+    /// Whether the beep was on when sound events were last polled
+    beeping: bool,
+    /// Sound timer value after each timer tick of the last update
+    timer_ticks: Vec<u8>,
+    /// While paused, updates don't run any cycles or accumulate time
+    paused: bool,
+    /// While frozen, timer frames still happen but don't decrement the timers
+    timers_frozen: bool,
+}
+
+/// A change in whether the beep should be played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundEvent {
+    /// The sound timer became active.
+    Start,
+    /// The sound timer reached zero.
+    Stop,
 }
 
 pub enum Chip8RunnerResult {
-    HitBreakpoint,
-    Ok,
+    /// A breakpoint was hit after running `cycles` CPU cycles.
+    HitBreakpoint { cycles: usize },
+    /// The update finished after running `cycles` CPU cycles.
+    Ok { cycles: usize },
+}
+
+impl Chip8RunnerResult {
+    /// Returns the number of CPU cycles that ran.
+    pub fn cycles(&self) -> usize {
+        match self {
+            Chip8RunnerResult::HitBreakpoint { cycles } | Chip8RunnerResult::Ok { cycles } => {
+                *cycles
+            }
+        }
+    }
 }
 
 impl Chip8Runner {
     pub fn new(chip8: Chip8) -> Self {
         Self {
             chip8,
+            cpu_time_step: 1.0 / DEFAULT_CPU_HZ,
+            max_dt: DEFAULT_MAX_DT,
+            skip_idle_loops: false,
+            accurate_timing: false,
             cpu_dt_accumulator: 0.0,
             timer_dt_accumulator: 0.0,
+            recording: None,
+            pending_keys: None,
+            replay: None,
+            beeping: false,
+            timer_ticks: Vec::new(),
+            paused: false,
+            timers_frozen: false,
         }
     }
 
     /// Update emulator by delta time, handles both CPU and timer cycles.
     ///
     /// Runs as many CPU cycles and timer updates as needed based on the elapsed time `dt`.
+    /// `dt` is capped at `max_dt`, so after a host stall the emulation falls behind instead of
+    /// freezing to catch up on thousands of cycles.
     /// Returns early if a frame has to be rendered before the next CPU cycle.
+    /// Does nothing while paused.
     pub fn update(&mut self, dt: f32) -> Result<Chip8RunnerResult, Chip8Error> {
         self.update_with_breakpoints(dt, None)
     }
@@ -41,40 +111,311 @@ impl Chip8Runner {
     pub fn update_with_breakpoints(
         &mut self,
         dt: f32,
-        breakpoints: Option<&HashSet<u12>>,
+        breakpoints: Option<&dyn Breakpoints>,
     ) -> Result<Chip8RunnerResult, Chip8Error> {
+        self.timer_ticks.clear();
+        if self.paused {
+            return Ok(Chip8RunnerResult::Ok { cycles: 0 });
+        }
+
+        // The excess over max_dt is dropped on purpose
+        let dt = dt.min(self.max_dt);
         self.cpu_dt_accumulator += dt;
         self.timer_dt_accumulator += dt;
 
-        while self.timer_dt_accumulator >= TIMER_TIME_STEP {
-            self.timer_dt_accumulator -= TIMER_TIME_STEP;
-            self.chip8.timers_cycle();
-        }
+        let mut cycles = 0;
+
+        // Timer ticks and CPU cycles are interleaved in the order they are due,
+        // so a ROM polling the delay timer sees it decrement one tick at a time.
+        // The pending event with the largest overshoot of its accumulator happened earliest.
+        loop {
+            let cpu_overshoot = self.cpu_dt_accumulator - self.cpu_time_step;
+            let timer_overshoot = self.timer_dt_accumulator - TIMER_TIME_STEP;
+
+            if timer_overshoot >= 0.0 && timer_overshoot >= cpu_overshoot {
+                self.timer_dt_accumulator -= TIMER_TIME_STEP;
+                self.timer_frame();
+                continue;
+            }
+
+            if cpu_overshoot < 0.0 {
+                break;
+            }
+
+            if self.skip_idle_loops && self.chip8.is_halted_loop() {
+                // The loop can't be left by executing it, only the timers keep running
+                self.cpu_dt_accumulator = 0.0;
+                continue;
+            }
 
-        while self.cpu_dt_accumulator >= CPU_TIME_STEP {
-            self.cpu_dt_accumulator -= CPU_TIME_STEP;
+            self.cpu_dt_accumulator -= self.cpu_time_step * self.next_cost() as f32;
 
             let cpu_result = self.chip8.cpu_cycle()?;
+            cycles += 1;
 
             if let Some(breakpoints) = &breakpoints
-                && breakpoints.contains(&self.chip8.pc)
+                && breakpoints.should_break(&self.chip8)
             {
                 self.cpu_dt_accumulator = 0.0;
-                return Ok(Chip8RunnerResult::HitBreakpoint);
+                return Ok(Chip8RunnerResult::HitBreakpoint { cycles });
             }
 
             match cpu_result {
                 Chip8Result::WaitForNextFrame => {
                     // If we need to wait for the next frame we stop executing cycles.
                     // We clear the accumulator to avoid "catching up" in the next frame.
+                    // Timer ticks that are still due are processed by the next loop iterations.
                     self.cpu_dt_accumulator = 0.0;
-                    break;
                 }
                 Chip8Result::Continue => {}
             }
         }
 
-        Ok(Chip8RunnerResult::Ok)
+        Ok(Chip8RunnerResult::Ok { cycles })
+    }
+
+    /// Run up to `max_cycles` CPU cycles, regardless of elapsed time. Does nothing while paused.
+    ///
+    /// Each cycle advances the timers by one CPU clock period, so timers tick in proportion to
+    /// the budget used. An instruction waiting for the next frame uses up the cycles left until
+    /// the next timer tick without running instructions. With `set_skip_idle_loops`, a
+    /// jump-to-self loop uses up the rest of the budget the same way. The returned count only
+    /// includes cycles that ran an instruction.
+    pub fn run_budget(&mut self, max_cycles: usize) -> Result<Chip8RunnerResult, Chip8Error> {
+        self.run_budget_with_breakpoints(max_cycles, None)
+    }
+
+    /// Like `run_budget` but checks for breakpoints after each CPU cycle.
+    pub fn run_budget_with_breakpoints(
+        &mut self,
+        max_cycles: usize,
+        breakpoints: Option<&dyn Breakpoints>,
+    ) -> Result<Chip8RunnerResult, Chip8Error> {
+        self.timer_ticks.clear();
+        if self.paused {
+            return Ok(Chip8RunnerResult::Ok { cycles: 0 });
+        }
+
+        let mut cycles = 0;
+        let mut budget_used = 0;
+
+        while budget_used < max_cycles {
+            if self.skip_idle_loops && self.chip8.is_halted_loop() {
+                // The loop can't be left by executing it, only the timers keep running
+                self.advance_timers((max_cycles - budget_used) as f32 * self.cpu_time_step);
+                break;
+            }
+
+            self.advance_timers(self.cpu_time_step * self.next_cost() as f32);
+            budget_used += 1;
+
+            let cpu_result = self.chip8.cpu_cycle()?;
+            cycles += 1;
+
+            if let Some(breakpoints) = &breakpoints
+                && breakpoints.should_break(&self.chip8)
+            {
+                return Ok(Chip8RunnerResult::HitBreakpoint { cycles });
+            }
+
+            if let Chip8Result::WaitForNextFrame = cpu_result {
+                // Sit out the rest of the frame, the next timer tick ends it
+                let frame_left = TIMER_TIME_STEP - self.timer_dt_accumulator;
+                let idle_cycles = ((frame_left / self.cpu_time_step).ceil() as usize)
+                    .min(max_cycles - budget_used);
+                self.advance_timers(idle_cycles as f32 * self.cpu_time_step);
+                budget_used += idle_cycles;
+            }
+        }
+
+        Ok(Chip8RunnerResult::Ok { cycles })
+    }
+
+    /// Adds `dt` to the timer accumulator and runs the timer frames that became due.
+    fn advance_timers(&mut self, dt: f32) {
+        self.timer_dt_accumulator += dt;
+        while self.timer_dt_accumulator >= TIMER_TIME_STEP {
+            self.timer_dt_accumulator -= TIMER_TIME_STEP;
+            self.timer_frame();
+        }
+    }
+
+    /// Pause execution. Time passed to `update` while paused is discarded,
+    /// so the emulation doesn't catch up when resumed.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resume execution after `pause`.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns true if execution is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Restart the loaded ROM from the beginning, discarding any accumulated time.
+    pub fn reset(&mut self) {
+        self.chip8.reset();
+        self.cpu_dt_accumulator = 0.0;
+        self.timer_dt_accumulator = 0.0;
+    }
+
+    /// Start recording the keypad state once per timer frame (60Hz).
+    ///
+    /// While recording, keys set through the runner only reach the machine at the next timer
+    /// frame, the same point a replay applies them, so replaying the input reproduces the run.
+    /// Any previous recording is discarded.
+    pub fn enable_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    /// Returns the recorded keypad states as bitmasks (bit n = key n), one per timer frame.
+    pub fn recorded_input(&self) -> &[u16] {
+        self.recording.as_deref().unwrap_or_default()
+    }
+
+    /// Replay recorded keypad states, applying one per timer frame (60Hz).
+    ///
+    /// While replaying, the keypad is overwritten every frame. Combined with a seeded
+    /// random number generator this makes a run fully deterministic.
+    pub fn replay(&mut self, input: &[u16]) {
+        self.replay = Some((input.to_vec(), 0));
+    }
+
+    /// Returns true while recorded input is being replayed.
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Runs a 60Hz timer frame: applies input and updates the timers unless they are frozen.
+    fn timer_frame(&mut self) {
+        self.input_frame();
+        if !self.timers_frozen {
+            self.chip8.timers_cycle();
+        }
+        // Frozen frames are reported too, so there is one tick per frame like in recordings
+        self.timer_ticks.push(self.chip8.sound_timer);
+    }
+
+    /// Set whether the delay and sound timers are frozen while the CPU keeps running.
+    ///
+    /// Input recording and replay still advance once per timer frame.
+    pub fn freeze_timers(&mut self, frozen: bool) {
+        self.timers_frozen = frozen;
+    }
+
+    /// Returns true if the timers are frozen.
+    pub fn timers_frozen(&self) -> bool {
+        self.timers_frozen
+    }
+
+    /// Returns the sound timer value after each timer tick that happened during the last
+    /// `update` or `run_budget` call, in order.
+    ///
+    /// The length is the number of ticks. Since ticks are 1/60s apart, a front-end can use this
+    /// to time beeps more precisely than by polling `should_beep` once per rendered frame.
+    /// While the timers are frozen every tick reports the unchanged sound timer.
+    pub fn timer_ticks(&self) -> &[u8] {
+        &self.timer_ticks
+    }
+
+    /// Applies pending and replayed input and records the keypad state for the current timer frame.
+    fn input_frame(&mut self) {
+        if let Some(keys) = self.pending_keys.take() {
+            self.chip8.set_keys(keys);
+        }
+
+        if let Some((input, next)) = &mut self.replay {
+            match input.get(*next) {
+                Some(&mask) => {
+                    self.chip8
+                        .set_keys(std::array::from_fn(|key| mask & (1 << key) != 0));
+                    *next += 1;
+                }
+                None => self.replay = None,
+            }
+        }
+
+        if let Some(recording) = &mut self.recording {
+            let mask = (0..16)
+                .filter(|&key| self.chip8.keypad[key])
+                .fold(0u16, |mask, key| mask | (1 << key));
+            recording.push(mask);
+        }
+    }
+
+    /// Set the CPU clock speed in instructions per second. Panics if `hz` is not positive.
+    pub fn set_cpu_hz(&mut self, hz: f32) {
+        assert!(hz > 0.0, "CPU clock speed must be positive");
+        self.cpu_time_step = 1.0 / hz;
+    }
+
+    /// Get the CPU clock speed in instructions per second.
+    pub fn cpu_hz(&self) -> f32 {
+        1.0 / self.cpu_time_step
+    }
+
+    /// Set the largest time step processed by a single update, in seconds.
+    /// Panics if `max_dt` is not positive.
+    pub fn set_max_dt(&mut self, max_dt: f32) {
+        assert!(max_dt > 0.0, "Maximum time step must be positive");
+        self.max_dt = max_dt;
+    }
+
+    /// Get the largest time step processed by a single update, in seconds.
+    pub fn max_dt(&self) -> f32 {
+        self.max_dt
+    }
+
+    /// Set whether CPU cycles are skipped while the machine is in a jump-to-self loop.
+    ///
+    /// Timers keep running. Breakpoints are not checked while cycles are skipped.
+    pub fn set_skip_idle_loops(&mut self, skip: bool) {
+        self.skip_idle_loops = skip;
+    }
+
+    /// Set whether instructions take a varying number of CPU cycles, as on the COSMAC VIP.
+    ///
+    /// When enabled, each instruction uses up `Opcode::cost` CPU clock periods, so slow
+    /// instructions like Dxyn leave less time for the instructions that follow.
+    pub fn set_accurate_timing(&mut self, accurate: bool) {
+        self.accurate_timing = accurate;
+    }
+
+    /// Returns the number of CPU clock periods the next instruction takes.
+    fn next_cost(&self) -> u32 {
+        if self.accurate_timing {
+            // An instruction that can't be fetched fails when executed, its cost doesn't matter
+            self.chip8.next_opcode().map_or(1, |opcode| opcode.cost())
+        } else {
+            1
+        }
+    }
+
+    /// Returns true if the machine is in a jump-to-self loop.
+    pub fn is_idle(&self) -> bool {
+        self.chip8.is_halted_loop()
+    }
+
+    /// Returns true if the machine is waiting for a key to be pressed and released.
+    ///
+    /// Waiting is cheap: Fx0A waits for the next frame, so each update only runs the wait
+    /// instruction once and skips the rest of the frame's CPU cycles, while timers keep running.
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.chip8.is_waiting_for_key()
+    }
+
+    /// Get the current delay timer value.
+    pub fn delay_timer(&self) -> u8 {
+        self.chip8.delay_timer()
+    }
+
+    /// Get the current sound timer value.
+    pub fn sound_timer(&self) -> u8 {
+        self.chip8.sound_timer()
     }
 
     /// Returns true if the sound timer is active, indicating a beep should be played.
@@ -82,9 +423,54 @@ impl Chip8Runner {
         self.chip8.should_beep()
     }
 
+    /// Returns how much the XO-CHIP pitch raises the sound frequency, 1.0 at the default pitch.
+    pub fn sound_pitch_ratio(&self) -> f32 {
+        self.chip8.sound_pitch_ratio()
+    }
+
+    /// Returns a sound event if the beep started or stopped since the last call.
+    ///
+    /// Lets a front-end toggle its audio output only on transitions instead of every frame.
+    pub fn poll_sound_event(&mut self) -> Option<SoundEvent> {
+        let beeping = self.chip8.should_beep();
+        if beeping == self.beeping {
+            return None;
+        }
+
+        self.beeping = beeping;
+        Some(if beeping {
+            SoundEvent::Start
+        } else {
+            SoundEvent::Stop
+        })
+    }
+
     /// Set the state of a key on the keypad.
+    ///
+    /// While recording, the change is applied at the next timer frame.
     pub fn set_key(&mut self, key: u4, pressed: bool) {
-        self.chip8.set_key(key, pressed)
+        if self.recording.is_some() {
+            let keys = self.pending_keys.get_or_insert(self.chip8.keypad());
+            keys[usize::from(key)] = pressed;
+        } else {
+            self.chip8.set_key(key, pressed)
+        }
+    }
+
+    /// Set the state of all keys on the keypad at once.
+    ///
+    /// While recording, the change is applied at the next timer frame.
+    pub fn set_keys(&mut self, state: [bool; 16]) {
+        if self.recording.is_some() {
+            self.pending_keys = Some(state);
+        } else {
+            self.chip8.set_keys(state)
+        }
+    }
+
+    /// Get the state of the whole keypad (true = pressed).
+    pub fn keypad(&self) -> [bool; 16] {
+        self.chip8.keypad()
     }
 
     /// Get the state of a pixel on the display (true = on, false = off).
@@ -92,6 +478,21 @@ impl Chip8Runner {
         self.chip8.get_display_pixel(y, x)
     }
 
+    /// Get the planes a pixel is lit in as a bitmask (bit 0 = first plane, bit 1 = second plane).
+    pub fn get_display_planes(&self, y: usize, x: usize) -> u8 {
+        self.chip8.get_display_planes(y, x)
+    }
+
+    /// Renders the display as text, one line per row, using `on` and `off` for each pixel.
+    pub fn render_ascii(&self, on: char, off: char) -> String {
+        self.chip8.render_ascii(on, off)
+    }
+
+    /// Renders the display as text using Unicode half blocks, packing two rows into each line.
+    pub fn render_half_blocks(&self) -> String {
+        self.chip8.render_half_blocks()
+    }
+
     pub fn chip8_ref(&self) -> &Chip8 {
         &self.chip8
     }
@@ -99,4 +500,250 @@ impl Chip8Runner {
     pub fn chip8_mut(&mut self) -> &mut Chip8 {
         &mut self.chip8
     }
+
+    /// Consumes the runner and returns the emulator it owns.
+    pub fn into_chip8(self) -> Chip8 {
+        self.chip8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emu::Quirks;
+
+    /// 1200: jump to self.
+    const HALT: &[u8] = &[0x12, 0x00];
+    /// 1202: jump to the next instruction, 1200: jump back, so the loop never counts as idle.
+    const LOOP: &[u8] = &[0x12, 0x02, 0x12, 0x00];
+
+    fn runner(rom: &[u8]) -> Chip8Runner {
+        let mut chip8 = Chip8::new();
+        chip8.load(rom).unwrap();
+        Chip8Runner::new(chip8)
+    }
+
+    #[test]
+    fn run_budget_respects_budget() {
+        // At 1Hz the budget implies far more time than any update would process
+        let mut runner = runner(LOOP);
+        runner.set_cpu_hz(1.0);
+        assert_eq!(runner.run_budget(10).unwrap().cycles(), 10);
+        assert_eq!(runner.timer_ticks().len(), 600);
+    }
+
+    #[test]
+    fn run_budget_waits_for_next_frame() {
+        // D001: draw, which waits for the next frame, 1200: jump back
+        let mut runner = runner(&[0xD0, 0x01, 0x12, 0x00]);
+        runner.set_cpu_hz(600.0);
+
+        // Each 10 cycle frame runs a draw and a jump, the wait uses up the rest
+        let cycles = runner.run_budget(600).unwrap().cycles();
+        assert!((119..=121).contains(&cycles), "ran {cycles} cycles");
+        assert_eq!(runner.timer_ticks().len(), 60);
+    }
+
+    #[test]
+    fn run_budget_skips_idle_loops() {
+        let mut runner = runner(HALT);
+        runner.set_cpu_hz(600.0);
+        runner.set_skip_idle_loops(true);
+
+        assert_eq!(runner.run_budget(600).unwrap().cycles(), 0);
+        assert_eq!(runner.timer_ticks().len(), 60);
+    }
+
+    #[test]
+    fn timer_ticks_report_every_frame() {
+        let mut runner = runner(LOOP);
+        runner.set_cpu_hz(600.0);
+        runner.chip8_mut().sound_timer = 10;
+
+        runner.run_budget(30).unwrap();
+        assert_eq!(runner.timer_ticks(), [9, 8, 7]);
+
+        // Frozen frames still report a tick, with the sound timer unchanged
+        runner.freeze_timers(true);
+        runner.run_budget(20).unwrap();
+        assert_eq!(runner.timer_ticks(), [7, 7]);
+    }
+
+    #[test]
+    fn timers_tick_between_instructions() {
+        // A300: I = 0x300, then ten times F007: V0 = DT and F055: store V0 at I, I += 1
+        let mut rom = vec![0xA3, 0x00];
+        for _ in 0..10 {
+            rom.extend_from_slice(&[0xF0, 0x07, 0xF0, 0x55]);
+        }
+        let mut runner = runner(&rom);
+        // The timer ticks every 10.5 cycles, so it never ticks at the same time as a cycle
+        runner.set_cpu_hz(630.0);
+        runner.chip8_mut().delay_timer = 10;
+
+        let cycles = runner.update(21.5 / 630.0).unwrap().cycles();
+        assert_eq!(cycles, 21);
+
+        // The tick falls between the 10th and 11th cycle, after the fifth read
+        let observed = &runner.chip8_ref().memory()[0x300..0x30A];
+        assert_eq!(observed, [10, 10, 10, 10, 10, 9, 9, 9, 9, 9]);
+    }
+
+    #[test]
+    fn large_dt_is_capped() {
+        let mut runner = runner(LOOP);
+
+        // The default cap of 0.1s at 700Hz allows 70 cycles
+        let cycles = runner.update(10.0).unwrap().cycles();
+        assert!((69..=70).contains(&cycles), "ran {cycles} cycles");
+        assert!(runner.timer_ticks().len() <= 6);
+
+        runner.set_max_dt(0.05);
+        let cycles = runner.update(10.0).unwrap().cycles();
+        assert!((34..=36).contains(&cycles), "ran {cycles} cycles");
+    }
+
+    #[test]
+    fn update_reports_cycles_run() {
+        // Both are exact in binary, so the accumulator has no rounding error
+        let mut runner = runner(LOOP);
+        runner.set_cpu_hz(512.0);
+
+        assert_eq!(runner.update(1.0 / 16.0).unwrap().cycles(), 32);
+        assert_eq!(runner.update(1.0 / 64.0).unwrap().cycles(), 8);
+
+        // A breakpoint reports the cycles run until it was hit
+        let breakpoints = HashSet::from([u12::new(0x202)]);
+        let result = runner.update_with_breakpoints(1.0 / 16.0, Some(&breakpoints));
+        assert!(matches!(
+            result,
+            Ok(Chip8RunnerResult::HitBreakpoint { cycles: 1 })
+        ));
+    }
+
+    #[test]
+    fn paused_runner_runs_nothing() {
+        let mut runner = runner(LOOP);
+        runner.set_cpu_hz(512.0);
+        runner.chip8_mut().delay_timer = 10;
+
+        runner.pause();
+        assert_eq!(runner.update(0.1).unwrap().cycles(), 0);
+        assert_eq!(runner.run_budget(100).unwrap().cycles(), 0);
+        assert_eq!(runner.chip8_ref().pc(), u12::new(0x200));
+        assert_eq!(runner.chip8_ref().delay_timer(), 10);
+
+        // Time passed while paused isn't caught up on
+        runner.resume();
+        assert_eq!(runner.update(1.0 / 64.0).unwrap().cycles(), 8);
+    }
+
+    #[test]
+    fn frozen_timers_keep_their_value() {
+        let mut runner = runner(LOOP);
+        runner.chip8_mut().delay_timer = 10;
+        runner.chip8_mut().sound_timer = 10;
+
+        runner.freeze_timers(true);
+        assert!(runner.update(0.1).unwrap().cycles() > 0);
+        assert_eq!(runner.chip8_ref().delay_timer(), 10);
+        assert_eq!(runner.chip8_ref().sound_timer(), 10);
+
+        runner.freeze_timers(false);
+        runner.update(0.1).unwrap();
+        assert!(runner.chip8_ref().delay_timer() < 10);
+    }
+
+    #[test]
+    fn replayed_input_reproduces_the_display() {
+        // Draws the font digit of every pressed key, moving right after each one:
+        // 6000: V0 = 0, E09E: skip if key V0 pressed, 120C: next key, F029: I = digit V0,
+        // D125: draw at (V1, V2), 7105: V1 += 5, 7001: V0 += 1, 3010: skip if V0 == 16,
+        // 1202: check the next key, 1200: start over
+        let rom = [
+            0x60, 0x00, 0xE0, 0x9E, 0x12, 0x0C, 0xF0, 0x29, 0xD1, 0x25, 0x71, 0x05, 0x70, 0x01,
+            0x30, 0x10, 0x12, 0x02, 0x12, 0x00,
+        ];
+
+        let mut recorder = runner(&rom);
+        recorder.enable_recording();
+        for frame in 0..120 {
+            recorder.set_key(u4::new((frame / 7 % 16) as u8), frame % 5 < 3);
+            recorder.update(1.0 / 60.0).unwrap();
+        }
+
+        let mut player = runner(&rom);
+        player.replay(recorder.recorded_input());
+        for _ in 0..120 {
+            player.update(1.0 / 60.0).unwrap();
+        }
+
+        assert!(recorder.recorded_input().iter().any(|&mask| mask != 0));
+        assert_ne!(
+            recorder.chip8_ref().display_hash(),
+            Chip8::new().display_hash()
+        );
+        assert_eq!(
+            player.chip8_ref().display_hash(),
+            recorder.chip8_ref().display_hash()
+        );
+    }
+
+    #[test]
+    fn sound_timer_starts_and_stops_the_beep_once() {
+        let mut runner = runner(HALT);
+        runner.chip8_mut().sound_timer = 3;
+
+        let mut events = Vec::new();
+        events.extend(runner.poll_sound_event());
+        for _ in 0..10 {
+            runner.update(1.0 / 60.0).unwrap();
+            events.extend(runner.poll_sound_event());
+        }
+
+        assert_eq!(runner.sound_timer(), 0);
+        assert_eq!(events, [SoundEvent::Start, SoundEvent::Stop]);
+    }
+
+    #[test]
+    fn accurate_timing_runs_fewer_slow_instructions() {
+        // D015: draw 5 rows, which costs 7 cycles, 1200: jump back
+        let draw_loop = |accurate| {
+            let mut runner = runner(&[0xD0, 0x15, 0x12, 0x00]);
+            runner.chip8_mut().set_quirks(Quirks {
+                display_wait: false,
+                ..Quirks::default()
+            });
+            runner.set_cpu_hz(512.0);
+            runner.set_accurate_timing(accurate);
+            runner.update(1.0 / 16.0).unwrap().cycles()
+        };
+
+        assert_eq!(draw_loop(false), 32);
+        // Each draw and jump pair uses up 8 of the 32 clock periods
+        assert_eq!(draw_loop(true), 8);
+    }
+
+    #[test]
+    fn waiting_for_a_key_runs_one_cycle_per_update() {
+        // F00A: wait for a key into V0, 1202: jump to self
+        let mut runner = runner(&[0xF0, 0x0A, 0x12, 0x02]);
+        runner.set_cpu_hz(512.0);
+
+        let mut ticks = 0;
+        for _ in 0..3 {
+            assert_eq!(runner.update(1.0 / 16.0).unwrap().cycles(), 1);
+            assert!(runner.is_waiting_for_key());
+            ticks += runner.timer_ticks().len();
+        }
+        // The timers keep running at full speed meanwhile, 3/16s holds 11 ticks
+        assert_eq!(ticks, 11);
+
+        runner.set_key(u4::new(1), true);
+        runner.update(1.0 / 16.0).unwrap();
+        runner.set_key(u4::new(1), false);
+        runner.update(1.0 / 64.0).unwrap();
+        assert!(!runner.is_waiting_for_key());
+        assert_eq!(runner.update(1.0 / 16.0).unwrap().cycles(), 32);
+    }
 }
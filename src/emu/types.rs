@@ -13,17 +13,102 @@ pub enum Chip8Error {
     #[error("ROM is too large ({size} bytes), max size is {max_size} bytes")]
     RomLoadError { size: usize, max_size: usize },
 
+    #[error("Invalid ROM start address {address:#05X}, must be between {min:#05X} and {max:#05X}")]
+    InvalidStartAddress {
+        address: usize,
+        min: usize,
+        max: usize,
+    },
+
+    #[error("Invalid CPU clock speed {hz}, must be a positive number of instructions per second")]
+    InvalidCpuHz { hz: f32 },
+
+    #[error("Invalid maximum time step {max_dt}, must be a positive number of seconds")]
+    InvalidMaxDt { max_dt: f32 },
+
+    #[error("Failed to decode ROM: {reason}")]
+    RomDecodeError { reason: String },
+
+    #[error("Font is too large ({size} bytes), max size is {max_size} bytes")]
+    FontLoadError { size: usize, max_size: usize },
+
     #[error("Stack underflow: attempted to return from a subroutine with empty call stack")]
     StackUnderflow,
 
+    #[error("Stack overflow: attempted to call a subroutine with a full call stack")]
+    StackOverflow,
+
+    #[error("Memory access out of bounds at address {address:#06X}")]
+    MemoryOutOfBounds { address: u16 },
+
+    #[error("Misaligned program counter: instruction fetched from odd address {address:#05X}")]
+    MisalignedPc { address: u16 },
+
     #[error("Unknown opcode: {opcode:#06X}")]
     UnknownOpcode { opcode: u16 },
 
     #[error("Unknown ALU operation at opcode: {opcode:#06X}")]
     UnknownALUOpcode { opcode: u16 },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 }
 
 pub const DISPLAY_X: usize = 64;
 pub const DISPLAY_Y: usize = 32;
 /// A type alias for the CHIP-8 display buffer representation
 pub type Display<T> = [[T; DISPLAY_X]; DISPLAY_Y];
+
+/// Iterates over all display coordinates as (y, x), row by row.
+pub fn display_coords() -> impl Iterator<Item = (usize, usize)> {
+    (0..DISPLAY_Y).flat_map(|y| (0..DISPLAY_X).map(move |x| (y, x)))
+}
+
+/// Helper methods for [`Display`] buffers.
+pub trait DisplayExt<T> {
+    /// Iterates over all pixels as (y, x, value), row by row.
+    fn iter_coords<'a>(&'a self) -> impl Iterator<Item = (usize, usize, &'a T)>
+    where
+        T: 'a;
+
+    /// Creates a new display by applying `f` to every pixel.
+    ///
+    /// Named differently from the by-value array `map`, which would otherwise take precedence.
+    fn map_pixels<U>(&self, f: impl Fn(&T) -> U) -> Display<U>;
+}
+
+impl<T> DisplayExt<T> for Display<T> {
+    fn iter_coords<'a>(&'a self) -> impl Iterator<Item = (usize, usize, &'a T)>
+    where
+        T: 'a,
+    {
+        display_coords().map(|(y, x)| (y, x, &self[y][x]))
+    }
+
+    fn map_pixels<U>(&self, f: impl Fn(&T) -> U) -> Display<U> {
+        std::array::from_fn(|y| std::array::from_fn(|x| f(&self[y][x])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_pixels_converts_every_pixel() {
+        let mut display: Display<bool> = [[false; DISPLAY_X]; DISPLAY_Y];
+        display[0][0] = true;
+        display[DISPLAY_Y - 1][DISPLAY_X - 1] = true;
+
+        let levels: Display<u8> = display.map_pixels(|&on| if on { 255 } else { 0 });
+        assert_eq!(levels[0][0], 255);
+        assert_eq!(levels[DISPLAY_Y - 1][DISPLAY_X - 1], 255);
+        assert_eq!(
+            levels
+                .iter_coords()
+                .filter(|(_, _, level)| **level == 255)
+                .count(),
+            2
+        );
+    }
+}
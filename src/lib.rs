@@ -2,4 +2,8 @@ pub mod debugger;
 pub mod emu;
 
 mod types;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub use types::*;
+
+pub use emu::{Chip8, Chip8Error, Chip8Runner};
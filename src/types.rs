@@ -1,4 +1,5 @@
 use std::fmt;
+use std::hash::Hash;
 use std::ops::{Index, IndexMut};
 
 macro_rules! define_uint {
@@ -20,6 +21,15 @@ macro_rules! define_uint {
                 Self(value)
             }
 
+            /// Creates a new instance, or returns `None` if the value exceeds the maximum.
+            pub const fn try_new(value: $repr) -> Option<Self> {
+                if value <= $max {
+                    Some(Self(value))
+                } else {
+                    None
+                }
+            }
+
             pub const fn wrapping_add(self, rhs: $repr) -> Self {
                 Self((self.0.wrapping_add(rhs)) & $max)
             }
@@ -29,6 +39,17 @@ macro_rules! define_uint {
             }
         }
 
+        impl TryFrom<$repr> for $name {
+            type Error = OutOfRangeError;
+
+            fn try_from(value: $repr) -> Result<Self, Self::Error> {
+                Self::try_new(value).ok_or(OutOfRangeError {
+                    value: value.into(),
+                    max: $max,
+                })
+            }
+        }
+
         impl From<$name> for usize {
             fn from(v: $name) -> usize {
                 usize::from(v.0)
@@ -63,6 +84,14 @@ macro_rules! define_uint {
     };
 }
 
+/// Error returned when a value doesn't fit in a `u4` or `u12`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("value {value:#X} is out of range, max is {max:#X}")]
+pub struct OutOfRangeError {
+    pub value: u32,
+    pub max: u32,
+}
+
 define_uint!(
     /// A 4-bit unsigned integer.
     u4, u8, 0x0F
@@ -72,3 +101,26 @@ define_uint!(
     /// A 12-bit unsigned integer.
     u12, u16, 0x0FFF
 );
+
+// Breakpoints are stored in hash sets and maps keyed by address, so the types must stay hashable
+const _: () = {
+    const fn assert_hash<T: Hash + Eq>() {}
+    assert_hash::<u4>();
+    assert_hash::<u12>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_new_accepts_values_up_to_the_maximum() {
+        assert_eq!(u4::try_new(0xF).map(usize::from), Some(0xF));
+        assert_eq!(u4::try_new(0x10), None);
+        assert_eq!(u12::try_new(0xFFF).map(usize::from), Some(0xFFF));
+        assert_eq!(u12::try_new(0x1000), None);
+
+        assert!(u12::try_from(0x1000).is_err());
+        assert_eq!(u12::new(0xFFF).wrapping_add(1), u12::new(0));
+    }
+}
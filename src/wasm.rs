@@ -0,0 +1,150 @@
+//! A minimal wrapper for driving the emulator from JavaScript when built for WebAssembly.
+//!
+//! The functions are exported with the C ABI, so no bindings generator is needed: JavaScript
+//! calls them through `WebAssembly.Instance.exports` and reads the display straight out of the
+//! exported memory. Build the module with
+//!
+//! ```text
+//! cargo build --lib --release --target wasm32-unknown-unknown --no-default-features --features wasm
+//! ```
+//!
+//! The wasm target isn't needed to check the wrapper: `cargo build --features wasm` compiles the
+//! same code for the host, and its tests run there too.
+
+use crate::{
+    Chip8, Chip8Runner,
+    emu::{DISPLAY_X, DISPLAY_Y},
+    u4,
+};
+
+/// A runner plus the buffer its display is copied into for JavaScript to read.
+pub struct WasmEmulator {
+    runner: Chip8Runner,
+    /// One byte per pixel, row by row, holding the planes the pixel is lit in
+    display: [u8; DISPLAY_X * DISPLAY_Y],
+}
+
+/// Creates an emulator with no ROM loaded. Free it with `chip8_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn chip8_new() -> *mut WasmEmulator {
+    Box::into_raw(Box::new(WasmEmulator {
+        runner: Chip8Runner::new(Chip8::new()),
+        display: [0; DISPLAY_X * DISPLAY_Y],
+    }))
+}
+
+/// Frees an emulator created by `chip8_new`.
+///
+/// # Safety
+///
+/// `emu` must come from `chip8_new` and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_free(emu: *mut WasmEmulator) {
+    drop(unsafe { Box::from_raw(emu) });
+}
+
+/// Allocates `len` zeroed bytes, for JavaScript to copy a ROM into before `chip8_load`.
+/// Free them with `chip8_dealloc`.
+#[unsafe(no_mangle)]
+pub extern "C" fn chip8_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()).cast()
+}
+
+/// Frees bytes allocated by `chip8_alloc`.
+///
+/// # Safety
+///
+/// `ptr` and `len` must come from a single `chip8_alloc` call, and `ptr` must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_dealloc(ptr: *mut u8, len: usize) {
+    drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) });
+}
+
+/// Loads the `len` bytes at `rom` and restarts the machine.
+/// Returns false if the ROM doesn't fit in memory.
+///
+/// # Safety
+///
+/// `emu` must come from `chip8_new`, and `rom` must point to `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_load(emu: *mut WasmEmulator, rom: *const u8, len: usize) -> bool {
+    let emu = unsafe { &mut *emu };
+    let rom = unsafe { std::slice::from_raw_parts(rom, len) };
+
+    if emu.runner.chip8_mut().load(rom).is_err() {
+        return false;
+    }
+    emu.runner.reset();
+    true
+}
+
+/// Advances the emulation by `dt` seconds. Returns false if execution failed,
+/// for example on an unknown opcode.
+///
+/// # Safety
+///
+/// `emu` must come from `chip8_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_update(emu: *mut WasmEmulator, dt: f32) -> bool {
+    let emu = unsafe { &mut *emu };
+    emu.runner.update(dt).is_ok()
+}
+
+/// Sets the state of a key. Keys past 0xF are ignored.
+///
+/// # Safety
+///
+/// `emu` must come from `chip8_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_set_key(emu: *mut WasmEmulator, key: u8, pressed: bool) {
+    let emu = unsafe { &mut *emu };
+    if let Some(key) = u4::try_new(key) {
+        emu.runner.set_key(key, pressed);
+    }
+}
+
+/// Returns a pointer to the display, `DISPLAY_X * DISPLAY_Y` bytes row by row with the
+/// planes each pixel is lit in (bit 0 = first plane, bit 1 = second plane).
+///
+/// The buffer is only refreshed by this call and stays valid until the emulator is freed.
+///
+/// # Safety
+///
+/// `emu` must come from `chip8_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_display(emu: *mut WasmEmulator) -> *const u8 {
+    let emu = unsafe { &mut *emu };
+    for y in 0..DISPLAY_Y {
+        for x in 0..DISPLAY_X {
+            emu.display[y * DISPLAY_X + x] = emu.runner.get_display_planes(y, x);
+        }
+    }
+    emu.display.as_ptr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rom_runs_through_the_exports() {
+        // F029: I = font sprite for V0 = 0, D005: draw it at (0, 0), 1204: jump to self
+        let rom = [0xF0, 0x29, 0xD0, 0x05, 0x12, 0x04];
+
+        unsafe {
+            let emu = chip8_new();
+            let buffer = chip8_alloc(rom.len());
+            buffer.copy_from_nonoverlapping(rom.as_ptr(), rom.len());
+            assert!(chip8_load(emu, buffer, rom.len()));
+            chip8_dealloc(buffer, rom.len());
+
+            chip8_set_key(emu, 0x10, true);
+            assert!(chip8_update(emu, 0.1));
+
+            // The top row of the 0 glyph is 0xF0
+            let display = std::slice::from_raw_parts(chip8_display(emu), DISPLAY_X * DISPLAY_Y);
+            assert_eq!(&display[..5], [1, 1, 1, 1, 0]);
+            chip8_free(emu);
+        }
+    }
+}
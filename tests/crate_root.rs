@@ -0,0 +1,35 @@
+//! Runs a ROM through the API re-exported at the crate root.
+
+use chip8_rust::{Chip8, Chip8Runner};
+
+/// Computes 5 + 3, stores its BCD digits at 0x300, then adds 100 in a subroutine and stores
+/// the digits again at 0x303 before halting.
+const ROM: &[u8] = &[
+    0x60, 0x05, // 200: V0 = 5
+    0x61, 0x03, // 202: V1 = 3
+    0x80, 0x14, // 204: V0 += V1
+    0xA3, 0x00, // 206: I = 0x300
+    0xF0, 0x33, // 208: BCD of V0 at I
+    0x22, 0x10, // 20A: call 0x210
+    0x12, 0x0C, // 20C: jump to self
+    0x00, 0x00, // 20E: padding
+    0x70, 0x64, // 210: V0 += 100
+    0xA3, 0x03, // 212: I = 0x303
+    0xF0, 0x33, // 214: BCD of V0 at I
+    0x00, 0xEE, // 216: return
+];
+
+#[test]
+fn runs_rom_through_crate_root() {
+    let mut chip8 = Chip8::new();
+    chip8.load(ROM).unwrap();
+
+    let mut runner = Chip8Runner::new(chip8);
+    let cycles = runner.run_budget(100).unwrap().cycles();
+    assert_eq!(cycles, 100);
+    assert!(runner.is_idle());
+
+    let chip8 = runner.into_chip8();
+    assert_eq!(chip8.memory()[0x300..0x306], [0, 0, 8, 1, 0, 8]);
+    assert!(chip8.stack().is_empty());
+}